@@ -11,6 +11,13 @@ impl Day01 {
         Self::default()
     }
 
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let line = Lines::from_bufread(notes, LinesOpt::RAW)?.single_line()?;
+        day.battles.extend(line.chars());
+        Ok(day)
+    }
+
     fn potions_needed(a: &[char]) -> usize {
         fn potions_needed(ch: &char, extra: usize) -> usize {
             match ch {
@@ -30,46 +37,42 @@ impl Day01 {
         a.iter().map(|ch| potions_needed(ch, extra)).sum()
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn part1(&mut self) -> Result<usize, Error> {
         Ok(self
             .battles
             .chunks(1)
             .map(Self::potions_needed)
-            .sum::<usize>()
-            .into())
+            .sum::<usize>())
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn part2(&mut self) -> Result<usize, Error> {
         Ok(self
             .battles
             .chunks(2)
             .map(Self::potions_needed)
-            .sum::<usize>()
-            .into())
+            .sum::<usize>())
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn part3(&mut self) -> Result<usize, Error> {
         Ok(self
             .battles
             .chunks(3)
             .map(Self::potions_needed)
-            .sum::<usize>()
-            .into())
+            .sum::<usize>())
     }
 }
 
 impl helper::Runner for Day01 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let line = Lines::from_bufread(file, LinesOpt::RAW)?.single_line()?;
-        self.battles.extend(line.chars());
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
@@ -12,6 +12,28 @@ impl Day02 {
         Self::default()
     }
 
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        let words = lines
+            .first()
+            .and_then(|line| line.strip_prefix("WORDS:"))
+            .ok_or_else(|| Error::InvalidInput("Notes don't start with WORDS:".into()))?;
+        if words.split(',').any(|word| word.is_empty()) {
+            return Err(Error::InvalidInput(format!("Empty word in {}", lines[0])));
+        }
+        day.words.extend(words.split(',').map(|s| s.to_string()));
+        if lines.get(1).is_none_or(|line| !line.trim().is_empty()) {
+            return Err(Error::InvalidInput(
+                "No blank line between the words and the inscription".into(),
+            ));
+        }
+        for line in lines[2..].iter() {
+            day.lines.push(line.into());
+        }
+        Ok(day)
+    }
+
     fn scan_runes(&self, cylinder: bool) -> usize {
         let mut found_runes: HashSet<(usize, usize)> = HashSet::default();
         let runes: Vec<Vec<char>> = self.lines.iter().map(|l| l.chars().collect()).collect();
@@ -84,7 +106,48 @@ impl Day02 {
         found_runes.len()
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
+    // Reference implementation for stress testing: tries every word in every direction from
+    // every cell.
+    fn scan_runes_brute(&self, cylinder: bool) -> usize {
+        let runes: Vec<Vec<char>> = self.lines.iter().map(|l| l.chars().collect()).collect();
+        let mut found_runes: HashSet<(usize, usize)> = HashSet::default();
+        let dirs: &[(isize, isize)] = if cylinder {
+            &[(1, 0), (-1, 0), (0, 1), (0, -1)]
+        } else {
+            &[(1, 0), (-1, 0)]
+        };
+
+        for (y, row) in runes.iter().enumerate() {
+            let w = row.len() as isize;
+            for x in 0..row.len() {
+                for word in self.words.iter() {
+                    'dir: for (dx, dy) in dirs.iter() {
+                        let mut cells = Vec::new();
+                        for (i, c) in word.chars().enumerate() {
+                            let i = i as isize;
+                            let mut cx = x as isize + dx * i;
+                            let cy = y as isize + dy * i;
+                            if cylinder {
+                                cx = cx.rem_euclid(w);
+                            }
+                            if cx < 0 || cx >= w || cy < 0 || cy >= runes.len() as isize {
+                                continue 'dir;
+                            }
+                            if runes[cy as usize][cx as usize] != c {
+                                continue 'dir;
+                            }
+                            cells.push((cx as usize, cy as usize));
+                        }
+                        found_runes.extend(cells);
+                    }
+                }
+            }
+        }
+
+        found_runes.len()
+    }
+
+    pub fn part1(&mut self) -> Result<usize, Error> {
         Ok(self
             .lines
             .iter()
@@ -93,41 +156,70 @@ impl Day02 {
                     .filter(|i| self.words.iter().any(|w| s[*i..].starts_with(w)))
                     .count()
             })
-            .sum::<usize>()
-            .into())
+            .sum::<usize>())
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.scan_runes(false).into())
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.scan_runes(false))
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.scan_runes(true).into())
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        Ok(self.scan_runes(true))
+    }
+
+    pub(crate) fn part2_brute(&mut self) -> Result<usize, Error> {
+        Ok(self.scan_runes_brute(false))
+    }
+
+    pub(crate) fn part3_brute(&mut self) -> Result<usize, Error> {
+        Ok(self.scan_runes_brute(true))
     }
 }
 
 impl helper::Runner for Day02 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        self.words.extend(
-            lines[0]
-                .strip_prefix("WORDS:")
-                .unwrap()
-                .split(',')
-                .map(|s| s.to_string()),
-        );
-        for line in lines[2..].iter() {
-            self.lines.push(line.into());
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part1_example() {
+        let notes =
+            "WORDS:THE,OWE,MES,ROD,HER\n\nAWAKEN THE POWER ADORNED WITH THE FLAMES BRIGHT IRE\n";
+        let mut day = Day02::from_notes(notes.as_bytes()).unwrap();
+        assert_eq!(day.part1().unwrap(), 4);
+    }
+
+    #[test]
+    fn malformed_notes() {
+        for notes in [
+            "",
+            "foo\n\nbar\n",
+            "WORDS:A\n",
+            "WORDS:A\nB\n",
+            "WORDS:A,,B\n\nAB\n",
+        ] {
+            assert!(
+                matches!(
+                    Day02::from_notes(notes.as_bytes()),
+                    Err(Error::InvalidInput(_))
+                ),
+                "{notes:?}"
+            );
+        }
+    }
+}
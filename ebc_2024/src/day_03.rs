@@ -1,5 +1,6 @@
 #[allow(unused_imports)]
 use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt};
+use std::collections::VecDeque;
 
 #[derive(Default)]
 pub struct Day03 {
@@ -11,6 +12,19 @@ impl Day03 {
         Self::default()
     }
 
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    day.grid.insert((x as isize, y as isize), 1);
+                }
+            }
+        }
+        Ok(day)
+    }
+
     fn dig(&mut self, diag: bool) -> usize {
         let mut removed = self.grid.len();
         let mut coords: Vec<(isize, isize)> = self.grid.keys().copied().collect();
@@ -53,37 +67,77 @@ impl Day03 {
         removed
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.dig(false).into())
+    // Reference implementation for stress testing: each block is as deep as it is steps from the
+    // nearest ground that isn't dug, searching inwards from the blocks at the edge.
+    fn dig_from_edge(&self, diag: bool) -> usize {
+        let dirs: &[(isize, isize)] = if diag {
+            &[
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (1, -1),
+                (-1, 1),
+                (1, 1),
+            ]
+        } else {
+            &[(-1, 0), (1, 0), (0, -1), (0, 1)]
+        };
+        let neighbors = |(x, y): (isize, isize)| dirs.iter().map(move |(dx, dy)| (x + dx, y + dy));
+
+        let mut depths: HashMap<(isize, isize), usize> = HashMap::default();
+        let mut work = VecDeque::new();
+        for block in self.grid.keys().copied() {
+            if neighbors(block).any(|n| !self.grid.contains_key(&n)) {
+                depths.insert(block, 1);
+                work.push_back(block);
+            }
+        }
+        while let Some(block) = work.pop_front() {
+            let depth = depths[&block] + 1;
+            for n in neighbors(block) {
+                if self.grid.contains_key(&n) && !depths.contains_key(&n) {
+                    depths.insert(n, depth);
+                    work.push_back(n);
+                }
+            }
+        }
+        depths.values().sum()
+    }
+
+    pub fn part1(&mut self) -> Result<usize, Error> {
+        Ok(self.dig(false))
+    }
+
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.dig(false))
+    }
+
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        Ok(self.dig(true))
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.dig(false).into())
+    pub(crate) fn part2_edge(&mut self) -> Result<usize, Error> {
+        Ok(self.dig_from_edge(false))
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.dig(true).into())
+    pub(crate) fn part3_edge(&mut self) -> Result<usize, Error> {
+        Ok(self.dig_from_edge(true))
     }
 }
 
 impl helper::Runner for Day03 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for (y, line) in lines.iter().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                if c == '#' {
-                    self.grid.insert((x as isize, y as isize), 1);
-                }
-            }
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
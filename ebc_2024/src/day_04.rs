@@ -11,6 +11,15 @@ impl Day04 {
         Self::default()
     }
 
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for line in lines.iter() {
+            day.nails.push(line.parse()?);
+        }
+        Ok(day)
+    }
+
     fn level_to(&self, level: isize) -> usize {
         self.nails
             .iter()
@@ -23,15 +32,15 @@ impl Day04 {
         self.level_to(min)
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.level().into())
+    pub fn part1(&mut self) -> Result<usize, Error> {
+        Ok(self.level())
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.level().into())
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.level())
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn part3(&mut self) -> Result<usize, Error> {
         let min = self.nails.iter().copied().min().unwrap();
         let max = self.nails.iter().copied().max().unwrap();
         let mut level_to = (max + min) / 2;
@@ -52,24 +61,32 @@ impl Day04 {
             }
             break;
         }
-        Ok(level_to_strikes.into())
+        Ok(level_to_strikes)
+    }
+
+    // Reference implementation for stress testing: tries every level between the lowest and the
+    // highest nail.
+    pub(crate) fn part3_scan(&mut self) -> Result<usize, Error> {
+        let min = self.nails.iter().copied().min().ok_or(Error::Unsolved)?;
+        let max = self.nails.iter().copied().max().ok_or(Error::Unsolved)?;
+        (min..=max)
+            .map(|level| self.level_to(level))
+            .min()
+            .ok_or(Error::Unsolved)
     }
 }
 
 impl helper::Runner for Day04 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for line in lines.iter() {
-            self.nails.push(line.parse()?);
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
@@ -2,9 +2,154 @@
 use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt};
 use std::collections::VecDeque;
 
-#[derive(Default)]
+// Most clappers kept in one block of a column before it is split in two
+const BLOCK: usize = 64;
+
+// A column of clapper ids, front first, kept as a list of short blocks.  Popping the front is
+// O(1).  An insert walks the list from whichever end is nearer to the block holding its position,
+// then shifts at most half of that block, so it costs O(len / BLOCK + BLOCK) and not O(1), but
+// the rest of the column never moves.
+#[derive(Clone, Default)]
+struct Column {
+    blocks: VecDeque<VecDeque<u32>>,
+    len: usize,
+}
+
+impl Column {
+    fn from_ids(ids: &[u32]) -> Self {
+        Self {
+            blocks: ids
+                .chunks(BLOCK / 2)
+                .map(|ids| ids.iter().copied().collect())
+                .collect(),
+            len: ids.len(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn people(&self) -> impl Iterator<Item = u32> + '_ {
+        self.blocks.iter().flatten().copied()
+    }
+
+    fn front(&self) -> Option<u32> {
+        self.blocks.front()?.front().copied()
+    }
+
+    fn push_back(&mut self, id: u32) {
+        match self.blocks.back_mut() {
+            Some(block) if block.len() < BLOCK => block.push_back(id),
+            _ => self.blocks.push_back(VecDeque::from([id])),
+        }
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<u32> {
+        let block = self.blocks.front_mut()?;
+        let id = block.pop_front()?;
+        if block.is_empty() {
+            self.blocks.pop_front();
+        }
+        self.len -= 1;
+        Some(id)
+    }
+
+    fn insert(&mut self, pos: usize, id: u32) {
+        if pos >= self.len {
+            self.push_back(id);
+            return;
+        }
+        // The block holding `pos` and where in it
+        let (mut idx, mut pos) = (0, pos);
+        if pos < self.len / 2 {
+            while pos >= self.blocks[idx].len() {
+                pos -= self.blocks[idx].len();
+                idx += 1;
+            }
+        } else {
+            let mut after = self.len - pos;
+            idx = self.blocks.len() - 1;
+            while after > self.blocks[idx].len() {
+                after -= self.blocks[idx].len();
+                idx -= 1;
+            }
+            pos = self.blocks[idx].len() - after;
+        }
+
+        let block = &mut self.blocks[idx];
+        block.insert(pos, id);
+        if block.len() > BLOCK {
+            let rest = block.split_off(BLOCK / 2);
+            self.blocks.insert(idx + 1, rest);
+        }
+        self.len += 1;
+    }
+}
+
+/// The front clappers' numbers written one after the other, kept as decimal digits so a shout
+/// can be any length.  Clapper numbers start at 1, so there are never leading zeros and a longer
+/// shout is always the bigger one.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Shout(String);
+
+impl Shout {
+    /// The shout multiplied by `n`, by long multiplication.
+    pub fn times(&self, n: usize) -> Self {
+        if n == 0 {
+            return Self("0".into());
+        }
+        let mut digits = Vec::with_capacity(self.0.len() + 20);
+        let mut carry = 0u128;
+        for digit in self.0.bytes().rev() {
+            carry += (digit - b'0') as u128 * n as u128;
+            digits.push(b'0' + (carry % 10) as u8);
+            carry /= 10;
+        }
+        while carry > 0 {
+            digits.push(b'0' + (carry % 10) as u8);
+            carry /= 10;
+        }
+        digits.reverse();
+        Self(String::from_utf8(digits).unwrap())
+    }
+}
+
+impl Ord for Shout {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.0.len(), &self.0).cmp(&(other.0.len(), &other.0))
+    }
+}
+
+impl PartialOrd for Shout {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for Shout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The lineup and shout after some round of the dance.
+pub struct DanceState {
+    pub round: usize,
+    /// Clapper numbers of each column, front first
+    pub columns: Vec<Vec<usize>>,
+    pub shout: Shout,
+}
+
+#[derive(Clone, Default)]
 pub struct Day05 {
-    columns: Vec<VecDeque<usize>>,
+    // Clapper numbers and the digits they shout, indexed by the ids in the columns
+    numbers: Vec<usize>,
+    digits: Vec<String>,
+    columns: Vec<Column>,
+    // Rounds danced so far
+    round: usize,
 }
 
 impl Day05 {
@@ -12,95 +157,337 @@ impl Day05 {
         Self::default()
     }
 
-    fn round(&mut self, round: usize) {
-        let round = round - 1;
-        let from = round % self.columns.len();
-        let to = (round + 1) % self.columns.len();
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for line in lines.iter() {
+            let numbers = line
+                .split_whitespace()
+                .map(|n| n.parse())
+                .collect::<Result<Vec<usize>, _>>()?;
+            if numbers.len() > day.columns.len() {
+                day.columns.resize(numbers.len(), Column::default());
+            }
+            for (i, n) in numbers.iter().enumerate() {
+                if *n == 0 {
+                    return Err(Error::InvalidInput("Clapper numbers start at 1".into()));
+                }
+                day.columns[i].push_back(day.numbers.len() as u32);
+                day.numbers.push(*n);
+                day.digits.push(n.to_string());
+            }
+        }
+        if day.columns.is_empty() {
+            return Err(Error::InvalidInput("No columns of clappers".into()));
+        }
+        Ok(day)
+    }
+
+    pub fn columns(&self) -> Vec<Vec<usize>> {
+        self.columns
+            .iter()
+            .map(|col| col.people().map(|id| self.numbers[id as usize]).collect())
+            .collect()
+    }
+
+    fn round(&mut self) -> Result<(), Error> {
+        let from = self.round % self.columns.len();
+        let to = (self.round + 1) % self.columns.len();
+        self.round += 1;
 
-        let clapper = self.columns[from].pop_front().unwrap();
+        let clapper = self.columns[from]
+            .pop_front()
+            .ok_or_else(|| Error::InvalidInput(format!("Column {} has no clappers", from + 1)))?;
         let cycle = self.columns[to].len() * 2;
-        let pos = (clapper - 1) % cycle;
+        if cycle == 0 {
+            return Err(Error::InvalidInput(format!(
+                "Column {} has no clappers",
+                to + 1
+            )));
+        }
+        let pos = (self.numbers[clapper as usize] - 1) % cycle;
 
         if pos > cycle / 2 {
             // right side, insert after pos
-            let pos = cycle - (pos + 1);
-            if pos == self.columns[to].len() {
-                self.columns[to].push_back(clapper);
-            } else {
-                self.columns[to].insert(pos + 1, clapper);
-            }
+            self.columns[to].insert(cycle - pos, clapper);
         } else {
             // left side, insert before pos
             self.columns[to].insert(pos, clapper);
         }
+        Ok(())
+    }
+
+    fn shout(&self) -> Result<Shout, Error> {
+        let mut shout = String::new();
+        for (idx, col) in self.columns.iter().enumerate() {
+            let front = col.front().ok_or_else(|| {
+                Error::InvalidInput(format!("Column {} has no clappers", idx + 1))
+            })?;
+            shout.push_str(&self.digits[front as usize]);
+        }
+        Ok(Shout(shout))
     }
 
-    fn shout(&self) -> usize {
-        self.columns.iter().fold(0, |shout, col| {
-            shout * 10usize.pow((10 * col[0]).ilog(10)) + col[0]
+    // Everything which decides how the dance continues: the columns, each ended by u32::MAX, and
+    // which of them claps next.
+    fn state(&self) -> Vec<u32> {
+        let mut state = Vec::new();
+        for col in self.columns.iter() {
+            state.extend(col.people());
+            state.push(u32::MAX);
+        }
+        state.push((self.round % self.columns.len()) as u32);
+        state
+    }
+
+    fn restore(&mut self, state: &[u32], round: usize) {
+        let columns = state[..state.len() - 1].split(|id| *id == u32::MAX);
+        for (col, ids) in self.columns.iter_mut().zip(columns) {
+            *col = Column::from_ids(ids);
+        }
+        self.round = round;
+    }
+
+    /// The dance after `rounds` rounds from the starting lineup.  Once the dance repeats the rest
+    /// of the rounds are skipped, so `rounds` can be far beyond what could be danced.
+    pub fn state_after(&self, rounds: usize) -> Result<DanceState, Error> {
+        let mut dance = self.clone();
+        let mut history = vec![dance.state()];
+        let mut seen: HashMap<Vec<u32>, usize> = HashMap::default();
+        seen.insert(history[0].clone(), 0);
+        for round in 1..=rounds {
+            dance.round()?;
+            let state = dance.state();
+            if let Some(start) = seen.insert(state.clone(), round) {
+                let same = start + (rounds - start) % (round - start);
+                dance.restore(&history[same], rounds);
+                break;
+            }
+            history.push(state);
+        }
+
+        Ok(DanceState {
+            round: rounds,
+            columns: dance.columns(),
+            shout: dance.shout()?,
         })
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        for round in 1..=10 {
-            self.round(round);
+    pub fn part1(&mut self) -> Result<Shout, Error> {
+        Ok(self.state_after(10)?.shout)
+    }
+
+    pub fn part2(&mut self) -> Result<Shout, Error> {
+        const TARGET: usize = 2024;
+
+        // shouts[round] is the shout after that round, round 0 being the starting lineup
+        let mut shouts = vec![self.shout()?];
+        let mut counts: HashMap<Shout, usize> = HashMap::default();
+        counts.insert(shouts[0].clone(), 1);
+        let mut seen: HashMap<Vec<u32>, usize> = HashMap::default();
+        seen.insert(self.state(), 0);
+        loop {
+            self.round()?;
+            let round = self.round;
+            let shout = self.shout()?;
+            shouts.push(shout.clone());
+            let count = counts.entry(shout.clone()).or_default();
+            *count += 1;
+            if *count == TARGET {
+                return Ok(shout.times(round));
+            }
+
+            if let Some(start) = seen.insert(self.state(), round) {
+                // The shouts of rounds start + 1..=round now repeat forever, so work out which
+                // shout gets to the target first without dancing there.
+                let period = &shouts[start + 1..=round];
+                let mut offsets: HashMap<&Shout, Vec<usize>> = HashMap::default();
+                for (offset, shout) in period.iter().enumerate() {
+                    offsets.entry(shout).or_default().push(offset);
+                }
+                return offsets
+                    .iter()
+                    .map(|(shout, offsets)| {
+                        let needed = TARGET - counts.get(*shout).copied().unwrap_or(0) - 1;
+                        let periods = needed / offsets.len();
+                        let offset = offsets[needed % offsets.len()];
+                        (round + periods * period.len() + offset + 1, *shout)
+                    })
+                    .min_by_key(|(round, _)| *round)
+                    .map(|(round, shout)| shout.times(round))
+                    .ok_or(Error::Unsolved);
+            }
         }
-        Ok(self.shout().into())
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        let mut seen: HashMap<usize, usize> = HashMap::default();
-        seen.insert(self.shout(), 1);
-        for round in 1.. {
-            self.round(round);
-            let shout = self.shout();
-            let seen = seen.entry(shout).or_default();
-            *seen += 1;
-            if *seen == 2024 {
-                return Ok((round * shout).into());
+    // Reference implementation for stress testing: dances until a shout has been heard 2024
+    // times, however long that takes.
+    pub(crate) fn part2_dance(&mut self) -> Result<String, Error> {
+        let mut counts: HashMap<Shout, usize> = HashMap::default();
+        counts.insert(self.shout()?, 1);
+        loop {
+            self.round()?;
+            let shout = self.shout()?;
+            let count = counts.entry(shout.clone()).or_default();
+            *count += 1;
+            if *count == 2024 {
+                return Ok(shout.times(self.round).to_string());
             }
         }
-        Err(Error::Unsolved)
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        let mut seen: HashMap<usize, usize> = HashMap::default();
-        seen.insert(self.shout(), 1);
-        for round in 1..5000 {
-            self.round(round);
-            let shout = self.shout();
-            let seen = seen.entry(shout).or_default();
-            *seen += 1;
-            if *seen == 2024 {
-                return Ok((round * shout).into());
+    pub fn part3(&mut self) -> Result<Shout, Error> {
+        let mut max = self.shout()?;
+        let mut seen: HashSet<Vec<u32>> = HashSet::default();
+        seen.insert(self.state());
+        loop {
+            self.round()?;
+            max = max.max(self.shout()?);
+            if !seen.insert(self.state()) {
+                return Ok(max);
             }
         }
-        Ok(seen.keys().copied().max().unwrap().into())
     }
 }
 
 impl helper::Runner for Day05 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for line in lines.iter() {
-            let numbers = line
-                .split_whitespace()
-                .map(|n| n.parse().unwrap())
-                .collect::<Vec<usize>>();
-            self.columns.resize(numbers.len(), VecDeque::new());
-            for (i, n) in numbers.iter().enumerate() {
-                self.columns[i].push_back(*n);
-            }
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.to_string().into()),
+            2 => Ok(self.part2()?.to_string().into()),
+            3 => Ok(self.part3()?.to_string().into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2 3 4 5\n3 4 5 2\n4 5 2 3\n5 2 3 4\n";
+    const EXAMPLE_2: &str = "2 3 4 5\n6 7 8 9\n";
+
+    fn day(notes: &str) -> Day05 {
+        Day05::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    // The lineup after each round, danced without skipping up to `rounds`
+    fn dance(day: &Day05, rounds: usize) -> Vec<Vec<Vec<usize>>> {
+        let mut dance = day.clone();
+        let mut lineups = vec![dance.columns()];
+        for _ in 0..rounds {
+            dance.round().unwrap();
+            lineups.push(dance.columns());
+        }
+        lineups
+    }
+
+    #[test]
+    fn example_shouts() {
+        let day = day(EXAMPLE);
+        let shouts = [
+            "2345", "3345", "3245", "3255", "3252", "4252", "4452", "4422", "4423", "2423", "2323",
+        ];
+        for (round, shout) in shouts.iter().enumerate() {
+            assert_eq!(day.state_after(round).unwrap().shout.to_string(), *shout);
+        }
+        assert_eq!(day.clone().part1().unwrap().to_string(), "2323");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(day(EXAMPLE_2).part2().unwrap().to_string(), "50877075");
+    }
+
+    #[test]
+    fn part3_example() {
+        assert_eq!(day(EXAMPLE_2).part3().unwrap().to_string(), "6584");
+    }
+
+    #[test]
+    fn state_after_skips_repeats() {
+        for notes in [EXAMPLE, EXAMPLE_2] {
+            let day = day(notes);
+            let lineups = dance(&day, 2000);
+            for round in [0, 1, 9, 150, 1999] {
+                assert_eq!(day.state_after(round).unwrap().columns, lineups[round]);
+            }
+
+            // The first round whose lineup comes round again with the same column clapping next
+            let columns = day.columns.len();
+            let (start, repeat) = (0..lineups.len())
+                .flat_map(|start| (start + 1..lineups.len()).map(move |repeat| (start, repeat)))
+                .find(|(start, repeat)| {
+                    (repeat - start) % columns == 0 && lineups[*start] == lineups[*repeat]
+                })
+                .unwrap();
+            let period = repeat - start;
+            let rounds = start + 7 + period * 1_000_000_000_000;
+            assert_eq!(day.state_after(rounds).unwrap().columns, lineups[start + 7]);
+        }
+    }
+
+    #[test]
+    fn clapper_zero_is_rejected() {
+        assert!(matches!(
+            Day05::from_notes(b"2 0\n3 4\n"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn shouts_of_any_length() {
+        let notes = "1234567890 2234567890 3234567890 4234567890 5234567890\n\
+                     6234567891 7234567892 8234567893 9234567894 1034567895\n";
+        let shout = day(notes).part1().unwrap().to_string();
+        assert_eq!(shout.len(), 50);
+
+        let shout = Shout("123456789012345678901234567890123456789012345".into());
+        assert_eq!(
+            shout.times(2024).to_string(),
+            "249876540960987654096098765409609876540960986280"
+        );
+        assert_eq!(shout.times(1), shout);
+        assert_eq!(shout.times(0).to_string(), "0");
+        assert!(Shout("9".into()) < Shout("10".into()));
+        assert!(Shout("1999".into()) < Shout("2000".into()));
+    }
+
+    #[test]
+    fn column_inserts_like_a_vec() {
+        let mut column = Column::default();
+        let mut ids = Vec::new();
+        for id in 0..2000u32 {
+            let pos = (id as usize * 7919) % (ids.len() + 1);
+            column.insert(pos, id);
+            ids.insert(pos, id);
+            if id % 5 == 0 {
+                assert_eq!(column.pop_front(), Some(ids.remove(0)));
+            }
+        }
+        assert_eq!(column.len(), ids.len());
+        assert_eq!(column.people().collect::<Vec<_>>(), ids);
+        assert_eq!(Column::from_ids(&ids).people().collect::<Vec<_>>(), ids);
+
+        // Inserting from either end of a rebuilt column, up to just past the last clapper
+        let mut column = Column::from_ids(&ids);
+        for (n, pos) in [0, 1, ids.len() / 2, ids.len() - 1, ids.len() + 1, 33, 64]
+            .into_iter()
+            .enumerate()
+        {
+            let id = 5000 + n as u32;
+            column.insert(pos, id);
+            ids.insert(pos.min(ids.len()), id);
+            column.push_back(id + 100);
+            ids.push(id + 100);
+        }
+        assert_eq!(column.len(), ids.len());
+        assert_eq!(column.people().collect::<Vec<_>>(), ids);
+    }
+}
@@ -1,5 +1,7 @@
 #[allow(unused_imports)]
 use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt};
+use std::io;
+use std::path::Path;
 
 #[derive(Debug)]
 pub enum Node {
@@ -8,13 +10,15 @@ pub enum Node {
     },
     Branch {
         name: String,
-        parent: usize,
+        /// `None` until the branch is listed under a parent, which it may never be
+        parent: Option<usize>,
         children: Vec<usize>,
-        depth: usize,
+        /// `None` if the branch can't be reached from the root
+        depth: Option<usize>,
     },
     Fruit {
         parent: usize,
-        depth: usize,
+        depth: Option<usize>,
     },
 }
 
@@ -22,7 +26,58 @@ impl Node {
     fn parent(&self) -> Option<usize> {
         match self {
             Self::Root { .. } => None,
-            Self::Branch { parent, .. } | Self::Fruit { parent, .. } => Some(*parent),
+            Self::Branch { parent, .. } => *parent,
+            Self::Fruit { parent, .. } => Some(*parent),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Root { .. } => "RR",
+            Self::Branch { name, .. } => name,
+            Self::Fruit { .. } => "@",
+        }
+    }
+}
+
+/// Something in the notes that keeps them from describing a single tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// Branches which are each other's parents, in order going up, and how many fruits grow
+    /// from them
+    Cycle { names: Vec<String>, fruits: usize },
+    /// A branch never listed under a parent, and how many fruits grow from it
+    Orphan { name: String, fruits: usize },
+    /// A branch listed under more than one parent, or under the same one twice.  Only the first
+    /// listing is kept.
+    MultipleParents { name: String, parents: Vec<String> },
+    /// The root listed as a child, which is ignored
+    RootAsChild { parent: String },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |fruits: usize| if fruits == 1 { "" } else { "s" };
+        match self {
+            Self::Cycle { names, fruits } => write!(
+                f,
+                "Cycle {} -> {} cuts off {fruits} fruit{}",
+                names.join(" -> "),
+                names[0],
+                plural(*fruits)
+            ),
+            Self::Orphan { name, fruits } => write!(
+                f,
+                "{name} has no parent, cutting off {fruits} fruit{}",
+                plural(*fruits)
+            ),
+            Self::MultipleParents { name, parents } => write!(
+                f,
+                "{name} is listed under {}, kept under {}",
+                parents.join(", "),
+                parents[0]
+            ),
+            Self::RootAsChild { parent } => write!(f, "RR is listed under {parent}, ignored"),
         }
     }
 }
@@ -31,6 +86,7 @@ impl Node {
 pub struct Day06 {
     nodes: Vec<Node>,
     root: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Day06 {
@@ -38,167 +94,491 @@ impl Day06 {
         Self::default()
     }
 
-    fn path_to(&self, shorten_name: bool, fruit_idx: usize) -> String {
-        let mut path: Vec<String> = Vec::new();
-        let mut idx = fruit_idx;
-        while let Some(node) = self.nodes.get(idx) {
-            match node {
-                Node::Root { .. } => {
-                    path.push("RR".into());
-                    idx = usize::MAX;
+    /// Builds the tree from lines of `parent:child,child,...`, with `RR` the root and `@` a
+    /// fruit.  Anything that keeps the notes from being a single tree is kept as a
+    /// `Diagnostic` rather than rejected, and fruits which can't be reached from the root are
+    /// left without a depth.
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        let mut names: HashMap<String, usize> = HashMap::default();
+        // Every listing of each branch under a parent after the first
+        let mut extra_parents: Vec<(usize, Vec<usize>)> = Vec::new();
+        let mut root = None;
+        macro_rules! get_name {
+            ($name:expr, $parent_idx:expr) => {{
+                if $name == "@" {
+                    let id = day.nodes.len();
+                    day.nodes.push(Node::Fruit {
+                        parent: $parent_idx,
+                        depth: None,
+                    });
+                    id
+                } else {
+                    use std::collections::hash_map::Entry;
+                    match names.entry($name.into()) {
+                        Entry::Occupied(e) => *e.get(),
+                        Entry::Vacant(e) => {
+                            let id = day.nodes.len();
+                            e.insert(id);
+                            if $name == "RR" {
+                                root = Some(id);
+                                day.nodes.push(Node::Root {
+                                    children: Vec::new(),
+                                });
+                            } else {
+                                day.nodes.push(Node::Branch {
+                                    name: $name.into(),
+                                    parent: None,
+                                    children: Vec::new(),
+                                    depth: None,
+                                });
+                            }
+                            id
+                        }
+                    }
+                }
+            }};
+        }
+
+        for line in lines.iter() {
+            let Some((parent, children)) = line.split_once(':') else {
+                return Err(Error::InvalidInput(format!("No ':' in {line}")));
+            };
+            if parent == "@" {
+                return Err(Error::InvalidInput(format!("Fruit with children: {line}")));
+            }
+            if parent.is_empty() || children.split(',').any(|child| child.is_empty()) {
+                return Err(Error::InvalidInput(format!("Empty name in {line}")));
+            }
+            let parent_idx = get_name!(parent, usize::MAX);
+            for child in children.split(',') {
+                let child_idx = get_name!(child, parent_idx);
+                match day.nodes.get_mut(child_idx) {
+                    Some(Node::Root { .. }) => {
+                        day.diagnostics.push(Diagnostic::RootAsChild {
+                            parent: parent.into(),
+                        });
+                        continue;
+                    }
+                    Some(Node::Branch {
+                        parent: Some(first),
+                        ..
+                    }) => {
+                        match extra_parents.iter_mut().find(|(idx, _)| *idx == child_idx) {
+                            Some((_, parents)) => parents.push(parent_idx),
+                            None => extra_parents.push((child_idx, vec![*first, parent_idx])),
+                        }
+                        continue;
+                    }
+                    Some(Node::Branch { parent, .. }) => *parent = Some(parent_idx),
+                    _ => {}
                 }
-                Node::Branch { name, parent, .. } => {
-                    path.push(name.clone());
-                    idx = *parent;
+                match day.nodes.get_mut(parent_idx) {
+                    Some(Node::Root { children }) | Some(Node::Branch { children, .. }) => {
+                        children.push(child_idx);
+                    }
+                    _ => unreachable!(),
                 }
-                Node::Fruit { parent, .. } => {
-                    path.push("@".into());
-                    idx = *parent;
+            }
+        }
+        day.root = root.ok_or_else(|| Error::InvalidInput("No root RR".into()))?;
+
+        for (idx, parents) in extra_parents {
+            day.diagnostics.push(Diagnostic::MultipleParents {
+                name: day.nodes[idx].name().into(),
+                parents: parents
+                    .iter()
+                    .map(|parent| day.nodes[*parent].name().into())
+                    .collect(),
+            });
+        }
+        for i in 0..day.nodes.len() {
+            day.get_depth(i);
+        }
+        day.find_detached();
+        Ok(day)
+    }
+
+    // Groups the branches and fruits that can't be reached from the root by the orphan or
+    // cycle they hang from.
+    fn find_detached(&mut self) {
+        // The top of each detached piece with the fruits under it, in order of the top
+        let mut pieces: Vec<(usize, usize)> = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match node {
+                Node::Branch { depth: None, .. } | Node::Fruit { depth: None, .. } => {}
+                _ => continue,
+            }
+            let mut seen = vec![idx];
+            let mut top = idx;
+            while let Some(parent) = self.nodes[top].parent() {
+                if seen.contains(&parent) {
+                    // Name a cycle by its first node so every way into it agrees
+                    let cycle = seen.iter().position(|seen| *seen == parent).unwrap();
+                    top = *seen[cycle..].iter().min().unwrap();
+                    break;
+                }
+                seen.push(parent);
+                top = parent;
+            }
+            let fruit = matches!(node, Node::Fruit { .. }) as usize;
+            match pieces.iter_mut().find(|(t, _)| *t == top) {
+                Some((_, fruits)) => *fruits += fruit,
+                None => pieces.push((top, fruit)),
+            }
+        }
+        pieces.sort();
+
+        for (top, fruits) in pieces {
+            let mut names = vec![self.nodes[top].name().to_string()];
+            let mut idx = top;
+            while let Some(parent) = self.nodes[idx].parent() {
+                if parent == top {
+                    break;
                 }
+                names.push(self.nodes[parent].name().into());
+                idx = parent;
             }
+            self.diagnostics
+                .push(if names.len() == 1 && self.nodes[top].parent().is_none() {
+                    Diagnostic::Orphan {
+                        name: names.pop().unwrap(),
+                        fruits,
+                    }
+                } else {
+                    Diagnostic::Cycle { names, fruits }
+                });
         }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
 
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The names from the root down to the fruit at `fruit_idx`.
+    pub fn path_to(&self, fruit_idx: usize) -> Vec<&str> {
+        let mut path = vec![self.nodes[fruit_idx].name()];
+        let mut idx = fruit_idx;
+        while let Some(parent) = self.nodes[idx].parent() {
+            path.push(self.nodes[parent].name());
+            idx = parent;
+        }
         path.reverse();
+        path
+    }
+
+    fn path_name(&self, shorten_name: bool, fruit_idx: usize) -> String {
+        let path = self.path_to(fruit_idx);
         if shorten_name {
-            path.iter().map(|s| &s[..1]).collect::<Vec<&str>>().join("")
+            path.iter().filter_map(|s| s.chars().next()).collect()
         } else {
             path.join("")
         }
     }
 
-    fn find_best_path(&self, shorten_name: bool) -> Result<String, Error> {
+    /// The fruit which is the only one at its depth, when exactly one is.
+    pub fn best_fruit(&self) -> Result<usize, Error> {
         let mut depths: HashMap<usize, Vec<usize>> = HashMap::default();
         for (idx, node) in self.nodes.iter().enumerate() {
-            if let Node::Fruit { depth, .. } = node {
-                if *depth != usize::MAX {
-                    depths.entry(*depth).or_default().push(idx);
-                }
+            if let Node::Fruit {
+                depth: Some(depth), ..
+            } = node
+            {
+                depths.entry(*depth).or_default().push(idx);
             }
         }
+        self.unique_fruit(&depths)
+    }
 
-        let min_share = depths
+    // Picks the only fruit alone at its depth, or explains why there isn't exactly one,
+    // including any fruits left out because they can't be reached from the root.
+    fn unique_fruit(&self, depths: &HashMap<usize, Vec<usize>>) -> Result<usize, Error> {
+        let mut alone: Vec<(usize, usize)> = depths
             .iter()
-            .fold((0, usize::MAX), |best, (depth, paths)| {
-                if paths.len() < best.1 {
-                    (*depth, paths.len())
-                } else {
-                    best
-                }
-            })
-            .0;
+            .filter(|(_, fruits)| fruits.len() == 1)
+            .map(|(depth, fruits)| (*depth, fruits[0]))
+            .collect();
+        if let [(_, fruit)] = alone[..] {
+            return Ok(fruit);
+        }
+        alone.sort();
+        let mut depths: Vec<(usize, usize)> = depths
+            .iter()
+            .map(|(depth, fruits)| (*depth, fruits.len()))
+            .collect();
+        depths.sort();
 
-        let paths = depths.get(&min_share).unwrap();
-        if paths.len() == 1 {
-            Ok(self.path_to(shorten_name, paths[0]))
+        let mut reason = if depths.is_empty() {
+            "no fruit can be reached from RR".to_string()
+        } else if alone.is_empty() {
+            let shared: Vec<String> = depths
+                .iter()
+                .map(|(depth, fruits)| format!("{fruits} at depth {depth}"))
+                .collect();
+            format!("every depth has several fruits ({})", shared.join(", "))
         } else {
-            Err(Error::Unsolved)
+            let alone: Vec<String> = alone.iter().map(|(depth, _)| depth.to_string()).collect();
+            format!("fruits are alone at depths {}", alone.join(", "))
+        };
+        for diagnostic in self.diagnostics.iter() {
+            if let Diagnostic::Cycle { fruits: 1.., .. } | Diagnostic::Orphan { fruits: 1.., .. } =
+                diagnostic
+            {
+                reason.push_str(&format!("; {diagnostic}"));
+            }
+        }
+        Err(Error::InvalidInput(format!("No unique path: {reason}")))
+    }
+
+    fn find_best_path(&self, shorten_name: bool) -> Result<String, Error> {
+        Ok(self.path_name(shorten_name, self.best_fruit()?))
+    }
+
+    /// The tree as a Graphviz digraph from the root down, each edge labelled with the depth of
+    /// the node it leads to.  The path to the best fruit is drawn in red, and the branches and
+    /// fruits which can't be reached from the root go in a cluster of their own.
+    pub fn dot(&self) -> String {
+        let best = self.best_fruit().ok();
+        let mut on_path = vec![false; self.nodes.len()];
+        let mut at = best;
+        while let Some(idx) = at {
+            on_path[idx] = true;
+            at = self.nodes[idx].parent();
+        }
+
+        // Names are quoted, so any quotes or backslashes in them need escaping
+        let escape = |name: &str| name.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph tree {\n    node [shape=box];\n");
+        let mut detached = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let (shape, depth) = match node {
+                Node::Root { .. } => ("doublecircle", Some(0)),
+                Node::Branch { depth, .. } => ("box", *depth),
+                Node::Fruit { depth, .. } => ("circle", *depth),
+            };
+            let color = if on_path[idx] {
+                ", color=red, penwidth=2"
+            } else {
+                ""
+            };
+            let fill = if let Node::Fruit { .. } = node {
+                ", style=filled, fillcolor=orange"
+            } else {
+                ""
+            };
+            let line = format!(
+                "n{idx} [label=\"{}\", shape={shape}{fill}{color}];\n",
+                escape(node.name())
+            );
+            if depth.is_some() {
+                dot.push_str(&format!("    {line}"));
+            } else {
+                detached.push(line);
+            }
+        }
+
+        if !detached.is_empty() {
+            dot.push_str("    subgraph cluster_detached {\n");
+            dot.push_str("        label=\"Not reachable from RR\";\n        style=dashed;\n");
+            for line in detached {
+                dot.push_str(&format!("        {line}"));
+            }
+            dot.push_str("    }\n");
+        }
+
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if let Node::Root { children } | Node::Branch { children, .. } = node {
+                for child in children.iter() {
+                    let depth = match &self.nodes[*child] {
+                        Node::Branch { depth, .. } | Node::Fruit { depth, .. } => *depth,
+                        Node::Root { .. } => None,
+                    };
+                    let label = depth.map_or("?".to_string(), |depth| depth.to_string());
+                    let color = if on_path[*child] {
+                        ", color=red, fontcolor=red, penwidth=2"
+                    } else {
+                        ""
+                    };
+                    dot.push_str(&format!(
+                        "    n{idx} -> n{child} [label=\"{label}\"{color}];\n"
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn write_dot(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.dot())
+    }
+
+    // Reference implementation for stress testing: finds fruit depths by walking down from the
+    // root instead of up from each fruit.
+    pub(crate) fn find_best_path_bfs(&self, shorten_name: bool) -> Result<String, Error> {
+        let mut depths: HashMap<usize, Vec<usize>> = HashMap::default();
+        let mut seen = HashSet::default();
+        let mut work = vec![(self.root, 0)];
+        seen.insert(self.root);
+        while let Some((idx, depth)) = work.pop() {
+            match &self.nodes[idx] {
+                Node::Root { children } | Node::Branch { children, .. } => {
+                    for child in children.iter() {
+                        if seen.insert(*child) {
+                            work.push((*child, depth + 1));
+                        }
+                    }
+                }
+                Node::Fruit { .. } => depths.entry(depth).or_default().push(idx),
+            }
         }
+
+        let fruit = self.unique_fruit(&depths)?;
+        Ok(self.path_name(shorten_name, fruit))
     }
 
-    fn get_depth(&mut self, idx: usize) -> usize {
+    // The number of steps up from a node to the root, if it gets there without going round a
+    // cycle or stopping at a branch with no parent.
+    fn get_depth(&mut self, idx: usize) -> Option<usize> {
         let mut path_depth = 0;
-        let mut parent = self.nodes[idx].parent();
+        let mut at = idx;
         let mut seen = HashSet::default();
         seen.insert(idx);
-        while let Some(parent_idx) = parent {
+        while let Some(parent_idx) = self.nodes[at].parent() {
             if !seen.insert(parent_idx) {
-                path_depth = usize::MAX;
-                break;
+                return None;
             }
             path_depth += 1;
-            parent = self.nodes[parent_idx].parent();
+            at = parent_idx;
+        }
+        if at != self.root {
+            return None;
         }
         match &mut self.nodes[idx] {
             Node::Root { .. } => {}
             Node::Branch { ref mut depth, .. } | Node::Fruit { ref mut depth, .. } => {
-                *depth = path_depth
+                *depth = Some(path_depth)
             }
         }
-        path_depth
+        Some(path_depth)
+    }
+
+    pub fn part1(&mut self) -> Result<String, Error> {
+        self.find_best_path(false)
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.find_best_path(false)?.into())
+    pub fn part2(&mut self) -> Result<String, Error> {
+        self.find_best_path(true)
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.find_best_path(true)?.into())
+    pub fn part3(&mut self) -> Result<String, Error> {
+        self.find_best_path(true)
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.find_best_path(true)?.into())
+    pub(crate) fn part1_bfs(&mut self) -> Result<String, Error> {
+        self.find_best_path_bfs(false)
+    }
+
+    pub(crate) fn part2_bfs(&mut self) -> Result<String, Error> {
+        self.find_best_path_bfs(true)
+    }
+
+    pub(crate) fn part3_bfs(&mut self) -> Result<String, Error> {
+        self.find_best_path_bfs(true)
     }
 }
 
 impl helper::Runner for Day06 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        let mut names: HashMap<String, usize> = HashMap::default();
-        macro_rules! get_name {
-            ($name:expr, $parent_idx:expr) => {{
-                if $name == "@" {
-                    let id = self.nodes.len();
-                    self.nodes.push(Node::Fruit {
-                        parent: $parent_idx,
-                        depth: usize::MAX,
-                    });
-                    id
-                } else {
-                    use std::collections::hash_map::Entry;
-                    match names.entry($name.into()) {
-                        Entry::Occupied(e) => *e.get(),
-                        Entry::Vacant(e) => {
-                            let id = self.nodes.len();
-                            e.insert(id);
-                            if $name == "RR" {
-                                self.root = id;
-                                self.nodes.push(Node::Root {
-                                    children: Vec::new(),
-                                });
-                            } else {
-                                self.nodes.push(Node::Branch {
-                                    name: $name.into(),
-                                    parent: $parent_idx,
-                                    children: Vec::new(),
-                                    depth: usize::MAX,
-                                });
-                            }
-                            id
-                        }
-                    }
-                }
-            }};
-        }
-
-        for line in lines.iter() {
-            let (parent, children) = line.split_once(':').unwrap();
-            let parent_idx = get_name!(parent, usize::MAX);
-            for child in children.split(',') {
-                let child_idx = get_name!(child, parent_idx);
-                match self.nodes.get_mut(parent_idx) {
-                    Some(Node::Root { children }) | Some(Node::Branch { children, .. }) => {
-                        children.push(child_idx);
-                    }
-                    _ => unreachable!(),
-                }
-                if let Some(Node::Branch { parent, .. }) = self.nodes.get_mut(child_idx) {
-                    *parent = parent_idx;
-                }
-            }
-        }
-
-        for i in 0..self.nodes.len() {
-            self.get_depth(i);
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "RR:A,B,C\nA:D,E\nB:F,@\nC:G,H\nD:@\nE:@\nF:@\nG:@\nH:@\n";
+
+    fn day(notes: &str) -> Day06 {
+        Day06::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn examples() {
+        let mut day = day(EXAMPLE);
+        assert!(day.diagnostics().is_empty());
+        assert_eq!(day.part1().unwrap(), "RRB@");
+        assert_eq!(day.part2().unwrap(), "RB@");
+        assert_eq!(day.part1_bfs().unwrap(), "RRB@");
+        assert_eq!(day.part2_bfs().unwrap(), "RB@");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let day = day("RR:A,B\nA:@\nB:C,@\nX:Y\nY:X,@\nZ:@,@\nC:A,RR\n");
+        assert_eq!(
+            day.diagnostics(),
+            [
+                Diagnostic::RootAsChild { parent: "C".into() },
+                Diagnostic::MultipleParents {
+                    name: "A".into(),
+                    parents: vec!["RR".into(), "C".into()]
+                },
+                Diagnostic::Cycle {
+                    names: vec!["X".into(), "Y".into()],
+                    fruits: 1
+                },
+                Diagnostic::Orphan {
+                    name: "Z".into(),
+                    fruits: 2
+                },
+            ]
+        );
+        // Only the fruits under A and B are reachable, and both are at depth 2
+        let err = day.best_fruit().unwrap_err();
+        assert!(matches!(&err, Error::InvalidInput(msg) if msg.contains("Cycle X -> Y -> X")));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            day("RR:Äx,B,Q\nÄx:@\nB:C\nC:@\nQ:D\nD:@\n")
+                .part2()
+                .unwrap(),
+            "RÄ@"
+        );
+        for notes in ["RR:A,,B\n", ":A\n", "RR:A\nA:\n"] {
+            assert!(matches!(
+                Day06::from_notes(notes.as_bytes()),
+                Err(Error::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn dot_escapes_names() {
+        let dot = day("RR:A,\"q\\\nA:@\n\"q\\:@,@\n").dot();
+        assert!(dot.contains(r#"[label="\"q\\", shape=box"#), "{dot}");
+        assert!(dot.contains("n0 -> n2 [label=\"1\"];"), "{dot}");
+    }
+}
@@ -2,18 +2,20 @@
 use helper::{
     print, println, Error, GroupedPermutations, HashMap, HashSet, Lines, LinesOpt, Permutations,
 };
-use std::str::FromStr;
-
-#[derive(PartialOrd, Ord, PartialEq, Eq)]
-struct Device {
-    total: isize,
-    name: String,
-    val: isize,
-    commands: Vec<Command>,
+use std::{io, path::Path, str::FromStr};
+
+use crate::math::gcd;
+
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Device {
+    pub total: i128,
+    pub name: String,
+    pub val: isize,
+    pub commands: Vec<Command>,
 }
 
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
-enum Command {
+pub enum Command {
     Inc,
     Dec,
     Stay,
@@ -86,13 +88,555 @@ impl Device {
             Command::Dec => self.val -= 1,
             Command::StartEnd | Command::Stay => {}
         }
-        self.total += self.val;
+        self.total += self.val as i128;
+    }
+
+    pub fn score(&self, track: &[Command], laps: usize) -> i128 {
+        RaceScorer::new(track, self.commands.len(), laps).score(&self.commands)
+    }
+}
+
+/// Scores plans of one length on a track for a number of laps.
+///
+/// A change to a device's value is added to its total once for every step left in the race, so
+/// the total is a fixed part from the track's own `+` and `-` segments plus a weighted sum over
+/// the plan.  Which plan action is used on a step and whether the track overrides it only depend
+/// on the step modulo lcm(track length, plan length), so the weights are summed over one such
+/// period with every later repeat of a step added in closed form.
+pub struct RaceScorer {
+    base: i128,
+    weights: Vec<i128>,
+}
+
+impl RaceScorer {
+    pub fn new(track: &[Command], plan_len: usize, laps: usize) -> Self {
+        let steps = (laps * track.len()) as i128;
+
+        // Sum of steps left after `step` and every `every` steps after it
+        let remaining = |step: usize, every: usize| -> i128 {
+            let (step, every) = (step as i128, every as i128);
+            if step >= steps {
+                return 0;
+            }
+            let count = (steps - step + every - 1) / every;
+            count * (steps - step) - every * count * (count - 1) / 2
+        };
+
+        let mut base = 10 * steps;
+        for (step, segment) in track.iter().enumerate() {
+            match segment {
+                Command::Inc => base += remaining(step, track.len()),
+                Command::Dec => base -= remaining(step, track.len()),
+                Command::Stay | Command::StartEnd => {}
+            }
+        }
+
+        let mut weights = vec![0; plan_len];
+        if plan_len != 0 {
+            let period = track.len() / gcd(track.len(), plan_len) * plan_len;
+            for step in 0..period {
+                if matches!(track[step % track.len()], Command::Stay | Command::StartEnd) {
+                    weights[step % plan_len] += remaining(step, period);
+                }
+            }
+        }
+
+        Self { base, weights }
+    }
+
+    pub fn score(&self, plan: &[Command]) -> i128 {
+        self.base
+            + plan
+                .iter()
+                .zip(self.weights.iter())
+                .map(|(action, weight)| match action {
+                    Command::Inc => *weight,
+                    Command::Dec => -*weight,
+                    Command::Stay | Command::StartEnd => 0,
+                })
+                .sum::<i128>()
+    }
+}
+
+pub struct PlanReport {
+    pub best: Vec<Command>,
+    pub best_score: i128,
+    /// Number of plans per score, highest score first
+    pub distribution: Vec<(i128, usize)>,
+    /// Number of plans scoring more than each device from the notes
+    pub beats: Vec<(String, usize)>,
+}
+
+impl std::fmt::Display for PlanReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plans: usize = self.distribution.iter().map(|(_, count)| count).sum();
+        let best: Vec<String> = self.best.iter().map(|c| format!("{c:?}")).collect();
+        writeln!(
+            f,
+            "Best plan: {} scoring {}",
+            best.join(","),
+            self.best_score
+        )?;
+        for (rival, count) in self.beats.iter() {
+            writeln!(f, "Beats {rival}: {count} of {plans} plans")?;
+        }
+        writeln!(f, "Scores:")?;
+        for (score, count) in self.distribution.iter() {
+            writeln!(f, "{score:>16} {count:>8}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Each device's total essence at the end of every lap of a race.
+pub struct RaceProgress {
+    pub names: Vec<String>,
+    /// `totals[lap][device]`, starting with everyone on 0 before the first lap
+    pub totals: Vec<Vec<i128>>,
+}
+
+impl RaceProgress {
+    /// Device names ordered from first to last place after `lap`.
+    pub fn ranking(&self, lap: usize) -> String {
+        let mut order: Vec<(i128, &str)> = self.totals[lap]
+            .iter()
+            .copied()
+            .zip(self.names.iter().map(|n| n.as_str()))
+            .collect();
+        order.sort();
+        order.reverse();
+        order.into_iter().map(|(_, name)| name).collect()
+    }
+
+    pub fn write_svg(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.svg())
+    }
+
+    // Line chart of every device's total per lap, with lap 0 on the left and the lowest total
+    // at the bottom.
+    pub fn svg(&self) -> String {
+        const COLORS: &[&str] = &[
+            "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+        ];
+        const WIDTH: f64 = 800.;
+        const HEIGHT: f64 = 400.;
+        const MARGIN: f64 = 40.;
+
+        let laps = (self.totals.len() - 1).max(1) as f64;
+        let min = self.totals.iter().flatten().copied().min().unwrap_or(0);
+        let max = self.totals.iter().flatten().copied().max().unwrap_or(0);
+        let range = ((max - min) as f64).max(1.);
+        let x = |lap: usize| MARGIN + lap as f64 * (WIDTH - 2. * MARGIN) / laps;
+        let y =
+            |total: i128| HEIGHT - MARGIN - (total - min) as f64 * (HEIGHT - 2. * MARGIN) / range;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\">\n"
+        );
+        svg.push_str(&format!(
+            "<text x=\"{MARGIN}\" y=\"{}\" font-size=\"12\">{max}</text>\n",
+            MARGIN - 5.
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{MARGIN}\" y=\"{}\" font-size=\"12\">{min}</text>\n",
+            HEIGHT - MARGIN + 15.
+        ));
+        for (device, name) in self.names.iter().enumerate() {
+            let color = COLORS[device % COLORS.len()];
+            let points: Vec<String> = self
+                .totals
+                .iter()
+                .enumerate()
+                .map(|(lap, totals)| format!("{:.1},{:.1}", x(lap), y(totals[device])))
+                .collect();
+            svg.push_str(&format!(
+                "<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"2\" points=\"{}\"/>\n",
+                points.join(" ")
+            ));
+            let last = self.totals.len() - 1;
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{color}\" font-size=\"12\">{name}</text>\n",
+                x(last) + 4.,
+                y(self.totals[last][device])
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl std::fmt::Display for RaceProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>5}", "Lap")?;
+        for name in self.names.iter() {
+            write!(f, " {name:>12}")?;
+        }
+        writeln!(f, "  Ranking")?;
+        for (lap, totals) in self.totals.iter().enumerate() {
+            write!(f, "{lap:>5}")?;
+            for total in totals.iter() {
+                write!(f, " {total:>12}")?;
+            }
+            writeln!(f, "  {}", self.ranking(lap))?;
+        }
+        Ok(())
+    }
+}
+
+/// A closed loop of track traced from an `S` on the map.
+pub struct Track {
+    pub start: (usize, usize),
+    /// Map position of each segment, in racing order and ending at `start`
+    pub cells: Vec<(usize, usize)>,
+    pub segments: Vec<Command>,
+}
+
+impl Track {
+    // Clockwise, so turning right from heading `d` is heading `d + 1`
+    const DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+    fn is_segment(c: char) -> bool {
+        matches!(c, '+' | '-' | '=' | 'S')
+    }
+
+    /// Splits the track into a loop through each `S` on the map, in reading order of the `S`s.
+    /// Every piece of track has to belong to exactly one of the loops.
+    ///
+    /// Pieces of track can run side by side, so which neighbours a piece is joined to isn't
+    /// always clear from the map.  Each piece is joined to exactly two others though, so a piece
+    /// with only two possible joins takes both, one with two joins made drops the rest, and a join
+    /// which would close a loop without exactly one `S`, or link two `S`s, is dropped.  Only
+    /// when that settles nothing more is a join guessed, and backed out of if it leads nowhere.
+    pub fn trace_all(map: &[Vec<char>]) -> Result<Vec<Self>, Error> {
+        let mut cells = Vec::new();
+        for (y, row) in map.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if Self::is_segment(*c) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        let index: HashMap<(usize, usize), usize> = cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| (*cell, idx))
+            .collect();
+        let starts: Vec<usize> = (0..cells.len())
+            .filter(|idx| map[cells[*idx].1][cells[*idx].0] == 'S')
+            .collect();
+        if starts.is_empty() {
+            return Err(Error::InvalidInput("Track has no S".into()));
+        }
+
+        // Every pair of neighbouring pieces could be joined, each pair once
+        let mut links = Vec::new();
+        let mut joins = vec![Vec::new(); cells.len()];
+        for (idx, cell) in cells.iter().enumerate() {
+            for dir in Self::DIRS[..2].iter() {
+                if let Some(next) = Self::step(map, *cell, *dir) {
+                    joins[idx].push(links.len());
+                    joins[index[&next]].push(links.len());
+                    links.push((idx, index[&next]));
+                }
+            }
+        }
+        for (idx, (x, y)) in cells.iter().enumerate() {
+            if joins[idx].len() < 2 {
+                return Err(Error::InvalidInput(if map[*y][*x] == 'S' {
+                    format!("Track from S at ({x}, {y}) is broken")
+                } else {
+                    format!("Track at ({x}, {y}) is a dead end")
+                }));
+            }
+        }
+
+        // Pieces of track which can't reach any S
+        let mut reached = vec![false; cells.len()];
+        let mut work = starts.clone();
+        starts.iter().for_each(|idx| reached[*idx] = true);
+        while let Some(idx) = work.pop() {
+            for link in joins[idx].iter() {
+                let (a, b) = links[*link];
+                let next = if a == idx { b } else { a };
+                if !reached[next] {
+                    reached[next] = true;
+                    work.push(next);
+                }
+            }
+        }
+        if let Some(idx) = reached.iter().position(|reached| !reached) {
+            let (x, y) = cells[idx];
+            return Err(Error::InvalidInput(format!(
+                "Track at ({x}, {y}) does not lead back to an S"
+            )));
+        }
+
+        let dark: Vec<bool> = cells.iter().map(|(x, y)| (x + y) % 2 == 0).collect();
+        let is_start: Vec<bool> = cells.iter().map(|(x, y)| map[*y][*x] == 'S').collect();
+        let mut loops = Loops {
+            links: &links,
+            joins: &joins,
+            dark: &dark,
+            is_start: &is_start,
+            state: vec![Link::Open; links.len()],
+            used: vec![0; cells.len()],
+            end: (0..cells.len()).collect(),
+            starts: is_start.iter().map(|start| *start as usize).collect(),
+        };
+        let work: Vec<usize> = (0..cells.len()).collect();
+        let loops = loops
+            .settle(work)
+            .then(|| loops.solve())
+            .flatten()
+            .ok_or_else(|| {
+                Error::InvalidInput("Track can't be split into loops through each S".into())
+            })?;
+
+        Ok(starts
+            .iter()
+            .map(|start| {
+                let mut path = Vec::new();
+                let (mut prev, mut at) = (*start, loops.next(*start, usize::MAX));
+                while at != *start {
+                    path.push(cells[at]);
+                    (prev, at) = (at, loops.next(at, prev));
+                }
+                Self::from_path(map, cells[*start], path.into_iter())
+            })
+            .collect())
+    }
+
+    fn step(
+        map: &[Vec<char>],
+        (x, y): (usize, usize),
+        dir: (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let x = x.checked_add_signed(dir.0)?;
+        let y = y.checked_add_signed(dir.1)?;
+        map.get(y)?
+            .get(x)
+            .filter(|c| Self::is_segment(**c))
+            .map(|_| (x, y))
+    }
+
+    // Races run clockwise, so a loop traced the other way round is reversed.
+    fn from_path(
+        map: &[Vec<char>],
+        start: (usize, usize),
+        path: impl Iterator<Item = (usize, usize)>,
+    ) -> Self {
+        let mut cells: Vec<(usize, usize)> = path.collect();
+        cells.push(start);
+
+        let area: isize = cells
+            .iter()
+            .zip(cells.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 as isize * b.1 as isize - b.0 as isize * a.1 as isize)
+            .sum();
+        if area < 0 {
+            cells.pop();
+            cells.reverse();
+            cells.push(start);
+        }
+
+        let segments = cells
+            .iter()
+            .map(|(x, y)| map[*y][*x].to_string().parse().unwrap())
+            .collect();
+        Self {
+            start,
+            cells,
+            segments,
+        }
+    }
+}
+
+// Whether two neighbouring pieces of track are joined
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Link {
+    Open,
+    Used,
+    Cut,
+}
+
+// The joins between pieces of track settled so far while splitting it into loops.  Joined pieces
+// form paths, and `end` and `starts` are only kept up to date at the two ends of each path: the
+// piece at its other end, and how many `S`s are on it.
+#[derive(Clone)]
+struct Loops<'a> {
+    links: &'a [(usize, usize)],
+    joins: &'a [Vec<usize>],
+    // Pieces coloured like a chessboard, which every loop on the grid alternates between
+    dark: &'a [bool],
+    is_start: &'a [bool],
+    state: Vec<Link>,
+    used: Vec<usize>,
+    end: Vec<usize>,
+    starts: Vec<usize>,
+}
+
+impl Loops<'_> {
+    // The piece joined to `at` other than `from`
+    fn next(&self, at: usize, from: usize) -> usize {
+        self.joins[at]
+            .iter()
+            .filter(|link| self.state[**link] == Link::Used)
+            .map(|link| {
+                let (a, b) = self.links[*link];
+                if a == at {
+                    b
+                } else {
+                    a
+                }
+            })
+            .find(|next| *next != from)
+            .unwrap()
+    }
+
+    // Whether joining `a` and `b` still leaves every loop with exactly one S
+    fn can_use(&self, link: usize) -> bool {
+        let (a, b) = self.links[link];
+        if self.used[a] == 2 || self.used[b] == 2 {
+            return false;
+        }
+        if self.end[a] == b {
+            self.starts[a] == 1
+        } else {
+            self.starts[a] + self.starts[b] <= 1
+        }
+    }
+
+    fn decide(&mut self, link: usize, to: Link, work: &mut Vec<usize>) -> bool {
+        let (a, b) = self.links[link];
+        if to == Link::Used {
+            if !self.can_use(link) {
+                return false;
+            }
+            let (end_a, end_b) = (self.end[a], self.end[b]);
+            let starts = self.starts[a] + self.starts[b];
+            self.used[a] += 1;
+            self.used[b] += 1;
+            if end_a != b {
+                self.end[end_a] = end_b;
+                self.end[end_b] = end_a;
+                self.starts[end_a] = starts;
+                self.starts[end_b] = starts;
+                work.extend([end_a, end_b]);
+            }
+        }
+        self.state[link] = to;
+        work.extend([a, b]);
+        true
+    }
+
+    // Makes every join that is forced, returning false if some piece can't be joined up
+    fn settle(&mut self, mut work: Vec<usize>) -> bool {
+        while let Some(idx) = work.pop() {
+            let open: Vec<usize> = self.joins[idx]
+                .iter()
+                .copied()
+                .filter(|link| self.state[*link] == Link::Open)
+                .collect();
+            for link in open.iter() {
+                if !self.can_use(*link) && !self.decide(*link, Link::Cut, &mut work) {
+                    return false;
+                }
+            }
+            let open: Vec<usize> = open
+                .into_iter()
+                .filter(|link| self.state[*link] == Link::Open)
+                .collect();
+            let to = if self.used[idx] == 2 {
+                Link::Cut
+            } else {
+                match self.used[idx] + open.len() {
+                    n if n < 2 => return false,
+                    2 => Link::Used,
+                    _ => continue,
+                }
+            };
+            for link in open {
+                if self.state[link] == Link::Open && !self.decide(link, to, &mut work) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Whether every group of pieces still joined or joinable together has an S and as many dark
+    // pieces as light ones, which it needs to be split into loops
+    fn viable(&self) -> bool {
+        let mut seen = vec![false; self.joins.len()];
+        for first in 0..self.joins.len() {
+            if seen[first] {
+                continue;
+            }
+            seen[first] = true;
+            let (mut starts, mut balance) = (0, 0isize);
+            let mut work = vec![first];
+            while let Some(idx) = work.pop() {
+                starts += self.is_start[idx] as usize;
+                balance += if self.dark[idx] { 1 } else { -1 };
+                for link in self.joins[idx].iter() {
+                    if self.state[*link] == Link::Cut {
+                        continue;
+                    }
+                    let (a, b) = self.links[*link];
+                    let next = if a == idx { b } else { a };
+                    if !seen[next] {
+                        seen[next] = true;
+                        work.push(next);
+                    }
+                }
+            }
+            if starts == 0 || balance != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Guesses the first join left open at a piece with the fewest choices, then the other way
+    fn solve(self) -> Option<Self> {
+        if !self.viable() {
+            return None;
+        }
+        let Some(link) = (0..self.joins.len())
+            .filter(|idx| self.used[*idx] < 2)
+            .min_by_key(|idx| {
+                self.joins[*idx]
+                    .iter()
+                    .filter(|link| self.state[**link] == Link::Open)
+                    .count()
+            })
+            .and_then(|idx| {
+                self.joins[idx]
+                    .iter()
+                    .copied()
+                    .find(|link| self.state[*link] == Link::Open)
+            })
+        else {
+            return self.used.iter().all(|used| *used == 2).then_some(self);
+        };
+        for to in [Link::Used, Link::Cut] {
+            let mut guess = self.clone();
+            let mut work = Vec::new();
+            if guess.decide(link, to, &mut work) && guess.settle(work) {
+                if let Some(solved) = guess.solve() {
+                    return Some(solved);
+                }
+            }
+        }
+        None
     }
 }
 
 #[derive(Default)]
 pub struct Day07 {
+    // Segments of the first track, which is the one raced on
     track: Vec<Command>,
+    tracks: Vec<Track>,
     devices: Vec<Device>,
 }
 
@@ -101,24 +645,108 @@ impl Day07 {
         Self::default()
     }
 
-    fn race_track(&mut self, laps: usize) -> String {
-        self.devices.iter_mut().for_each(Device::reset);
-        let mut lap = 0;
-        while lap < laps {
-            let lap_step = lap * self.track.len();
-            if lap != 0 && lap_step % self.devices[0].commands.len() == 0 {
-                let remaining_loops = (laps - lap) / lap;
-                lap += remaining_loops * lap;
-                if lap == laps {
-                    break;
-                }
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for (idx, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
             }
-            for (step, track) in self.track.iter().enumerate() {
-                self.devices
-                    .iter_mut()
-                    .for_each(|d| d.step(lap_step + step, Some(*track)));
+            if line.contains(':') {
+                day.devices.push(line.parse()?);
+            } else {
+                let map: Vec<Vec<char>> =
+                    lines[idx..].iter().map(|l| l.chars().collect()).collect();
+                day.tracks = Track::trace_all(&map)?;
+                day.track = day.tracks[0].segments.clone();
+                break;
             }
-            lap += 1;
+        }
+        Ok(day)
+    }
+
+    pub fn track(&self) -> &[Command] {
+        &self.track
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn devices(&self) -> &[Device] {
+        &self.devices
+    }
+
+    pub fn add_device(&mut self, device: Device) {
+        self.devices.push(device);
+    }
+
+    /// Redraws the map with only the traced tracks.
+    pub fn render_track(&self) -> String {
+        let cells = self
+            .tracks
+            .iter()
+            .flat_map(|t| t.cells.iter().zip(t.segments.iter()));
+        let width = cells.clone().map(|((x, _), _)| x + 1).max().unwrap_or(0);
+        let height = cells.clone().map(|((_, y), _)| y + 1).max().unwrap_or(0);
+        let mut map = vec![vec![' '; width]; height];
+        for ((x, y), segment) in cells {
+            map[*y][*x] = format!("{segment:?}").chars().next().unwrap();
+        }
+        let mut rendered = String::new();
+        for row in map.iter() {
+            rendered.extend(row.iter());
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    /// Races every device lap by lap round the `track`th track, recording their totals after each
+    /// lap.
+    pub fn race_progress(&self, track: usize, laps: usize) -> Result<RaceProgress, Error> {
+        let segments = &self
+            .tracks
+            .get(track)
+            .ok_or_else(|| Error::InvalidInput(format!("No track {track}")))?
+            .segments;
+        let mut devices = self.devices.clone();
+        devices.iter_mut().for_each(Device::reset);
+        let mut totals = vec![vec![0; devices.len()]];
+        for lap in 0..laps {
+            for step in lap * segments.len()..(lap + 1) * segments.len() {
+                let track = segments[step % segments.len()];
+                devices.iter_mut().for_each(|d| d.step(step, Some(track)));
+            }
+            totals.push(devices.iter().map(|d| d.total).collect());
+        }
+        Ok(RaceProgress {
+            names: devices.iter().map(|d| d.name.clone()).collect(),
+            totals,
+        })
+    }
+
+    pub fn race_track(&mut self, laps: usize) -> String {
+        let track = &self.track;
+        self.devices
+            .iter_mut()
+            .for_each(|d| d.total = d.score(track, laps));
+        self.devices.sort();
+        let mut rank = String::new();
+        self.devices
+            .iter()
+            .rev()
+            .for_each(|d| rank.push_str(&d.name));
+        rank
+    }
+
+    // Reference implementation for stress testing: races every lap without skipping ahead.
+    fn race_track_laps(&mut self, laps: usize) -> String {
+        self.devices.iter_mut().for_each(Device::reset);
+        for step in 0..laps * self.track.len() {
+            let track = self.track[step % self.track.len()];
+            self.devices
+                .iter_mut()
+                .for_each(|d| d.step(step, Some(track)));
         }
         self.devices.sort();
         let mut rank = String::new();
@@ -129,7 +757,63 @@ impl Day07 {
         rank
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
+    fn rival_score(&self, rival: &str, laps: usize) -> Result<i128, Error> {
+        self.devices
+            .iter()
+            .find(|d| d.name == rival)
+            .map(|d| d.score(&self.track, laps))
+            .ok_or_else(|| Error::InvalidInput(format!("No device named {rival}")))
+    }
+
+    /// Scores every distinct ordering of `actions` on the track.
+    pub fn optimize<const N: usize>(
+        &self,
+        actions: [(Command, usize); N],
+        laps: usize,
+    ) -> PlanReport {
+        let plan_len = actions.iter().map(|(_, count)| count).sum();
+        let scorer = RaceScorer::new(&self.track, plan_len, laps);
+
+        let mut best = Vec::new();
+        let mut best_score = i128::MIN;
+        let mut scores: HashMap<i128, usize> = HashMap::default();
+        let mut group_perms = GroupedPermutations::new(actions);
+        while let Some(commands) = group_perms.next_permutation() {
+            let score = scorer.score(commands);
+            *scores.entry(score).or_default() += 1;
+            if score > best_score {
+                best_score = score;
+                best = commands.into();
+            }
+        }
+
+        let mut distribution: Vec<(i128, usize)> = scores.into_iter().collect();
+        distribution.sort();
+        distribution.reverse();
+
+        let beats = self
+            .devices
+            .iter()
+            .map(|d| {
+                let rival = d.score(&self.track, laps);
+                let count = distribution
+                    .iter()
+                    .take_while(|(score, _)| *score > rival)
+                    .map(|(_, count)| count)
+                    .sum();
+                (d.name.clone(), count)
+            })
+            .collect();
+
+        PlanReport {
+            best,
+            best_score,
+            distribution,
+            beats,
+        }
+    }
+
+    pub fn part1(&mut self) -> Result<String, Error> {
         for step in 0..10 {
             self.devices.iter_mut().for_each(|d| d.step(step, None));
         }
@@ -139,141 +823,211 @@ impl Day07 {
             .iter()
             .rev()
             .for_each(|d| rank.push_str(&d.name));
-        Ok(rank.into())
+        Ok(rank)
+    }
+
+    pub fn part2(&mut self) -> Result<String, Error> {
+        Ok(self.race_track(10))
+    }
+
+    pub(crate) fn part2_laps(&mut self) -> Result<String, Error> {
+        Ok(self.race_track_laps(10))
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.race_track(10).into())
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        let report = self.optimize(
+            [(Command::Inc, 5), (Command::Dec, 3), (Command::Stay, 3)],
+            2024,
+        );
+        report
+            .beats
+            .iter()
+            .find(|(rival, _)| rival == "A")
+            .map(|(_, wins)| *wins)
+            .ok_or_else(|| Error::InvalidInput("No device named A".into()))
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
+    pub(crate) fn part3_permutations(&mut self) -> Result<usize, Error> {
+        let mut b = vec![
+            Command::Inc,
+            Command::Inc,
+            Command::Inc,
+            Command::Inc,
+            Command::Inc,
+            Command::Dec,
+            Command::Dec,
+            Command::Dec,
+            Command::Stay,
+            Command::Stay,
+            Command::Stay,
+        ];
+
+        let rival = self.rival_score("A", 2024)?;
+        let scorer = RaceScorer::new(&self.track, b.len(), 2024);
+
         let mut wins = 0;
-        if true {
-            let mut group_perms = GroupedPermutations::new([
-                (Command::Inc, 5),
-                (Command::Dec, 3),
-                (Command::Stay, 3),
-            ]);
-            while let Some(commands) = group_perms.next_permutation() {
-                self.devices.retain(|d| d.name == "A");
-                self.devices.push(Device {
-                    commands: commands.into(),
-                    total: 0,
-                    name: "B".into(),
-                    val: 0,
-                });
-
-                if self.race_track(2024) == "BA" && self.devices[1].total > self.devices[0].total {
-                    wins += 1;
-                }
+        let mut seen: HashSet<Vec<Command>> = HashSet::default();
+        Permutations::iter(&mut b, |commands| {
+            if seen.insert(commands.into()) && scorer.score(commands) > rival {
+                wins += 1;
             }
-        } else {
-            let mut b = vec![
-                Command::Inc,
-                Command::Inc,
-                Command::Inc,
-                Command::Inc,
-                Command::Inc,
-                Command::Dec,
-                Command::Dec,
-                Command::Dec,
-                Command::Stay,
-                Command::Stay,
-                Command::Stay,
-            ];
-
-            let mut wins = 0;
-            let mut seen: HashSet<Vec<Command>> = HashSet::default();
-            Permutations::iter(&mut b, |commands| {
-                if !seen.insert(commands.into()) {
-                    return;
-                }
-                self.devices.retain(|d| d.name == "A");
-                self.devices.push(Device {
-                    commands: commands.into(),
-                    total: 0,
-                    name: "B".into(),
-                    val: 0,
-                });
-
-                if self.race_track(2024) == "BA" && self.devices[1].total > self.devices[0].total {
-                    wins += 1;
-                }
-            });
-        }
+        });
 
-        Ok(wins.into())
+        Ok(wins)
     }
 }
 
 impl helper::Runner for Day07 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for (idx, line) in lines.iter().enumerate() {
-            if &line[1..=1] == ":" {
-                self.devices.push(line.parse()?);
-            } else {
-                // Parse track
-                let track: &[String] = &lines[idx..];
-
-                let mut track_map = HashMap::default();
-                for (y, line) in track.iter().enumerate() {
-                    for x in 0..line.len() {
-                        track_map.insert((x as isize, y as isize), &track[y][x..x + 1]);
-                    }
-                }
-
-                let mut xy = (0isize, 0isize);
-                let mut dxy = (1isize, 0isize);
-                loop {
-                    let next_xy = (xy.0 + dxy.0, xy.1 + dxy.1);
-                    match track_map.get(&next_xy).copied() {
-                        None | Some(" ") => {
-                            // Change Dir
-                            match dxy {
-                                (1, 0) | (-1, 0) => {
-                                    // Scan up/down
-                                    match track_map.get(&(xy.0, xy.1 - 1)).copied() {
-                                        Some("+") | Some("-") | Some("=") | Some("S") => {
-                                            dxy = (0, -1)
-                                        }
-                                        _ => dxy = (0, 1),
-                                    }
-                                }
-                                (0, 1) | (0, -1) => {
-                                    // Scan left/right
-                                    match track_map.get(&(xy.0 - 1, xy.1)).copied() {
-                                        Some("+") | Some("-") | Some("=") | Some("S") => {
-                                            dxy = (-1, 0)
-                                        }
-                                        _ => dxy = (1, 0),
-                                    }
-                                }
-                                _ => unreachable!(),
-                            }
-                        }
-                        Some("S") => {
-                            self.track.push(Command::StartEnd);
-                            break;
-                        }
-                        Some(s) => {
-                            self.track.push(s.parse()?);
-                            xy = next_xy;
-                        }
-                    }
-                }
-                break;
-            }
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{generator, Rng};
+
+    const DEVICES: &str = "A:+,-,=,=\nB:+,=,-,+\nC:=,-,+,+\nD:=,=,=,+\n";
+    const TRACK: &str = "S+===\n-   +\n=+=-+\n";
+
+    fn commands(s: &str) -> Vec<Command> {
+        s.chars().map(|c| c.to_string().parse().unwrap()).collect()
+    }
+
+    // A device raced one step at a time
+    fn simulate(track: &[Command], plan: &[Command], laps: usize) -> i128 {
+        let mut device = Device {
+            total: 0,
+            name: "P".into(),
+            val: 10,
+            commands: plan.into(),
+        };
+        for step in 0..laps * track.len() {
+            device.step(step, Some(track[step % track.len()]));
+        }
+        device.total
+    }
+
+    #[test]
+    fn part1_example() {
+        let mut day = Day07::from_notes(DEVICES.as_bytes()).unwrap();
+        assert_eq!(day.part1().unwrap(), "BDCA");
+    }
+
+    #[test]
+    fn part2_example() {
+        let notes = format!("{DEVICES}\n{TRACK}");
+        let mut day = Day07::from_notes(notes.as_bytes()).unwrap();
+        assert_eq!(day.part2().unwrap(), "DCBA");
+        assert_eq!(day.part2_laps().unwrap(), "DCBA");
+    }
+
+    // Every piece in the loop is a single step from the one before it, ending back at `S`
+    fn assert_loop(track: &Track) {
+        assert_eq!(track.cells.last(), Some(&track.start));
+        for (a, b) in track.cells.iter().zip(track.cells.iter().cycle().skip(1)) {
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1, "{a:?} to {b:?}");
+        }
+        let mut cells = track.cells.clone();
+        cells.sort();
+        cells.dedup();
+        assert_eq!(cells.len(), track.cells.len());
+    }
+
+    fn trace(map: &str) -> Result<Vec<Track>, Error> {
+        let map: Vec<Vec<char>> = map.lines().map(|l| l.chars().collect()).collect();
+        Track::trace_all(&map)
+    }
+
+    #[test]
+    fn traces_side_by_side_runs() {
+        // Below the first two rows every piece has track on at least three sides
+        let tracks =
+            trace("S=====\n+    -\n+=-+=+\n-+=-+=\n=+-=+-\n+-=+-+\n=+-=+=\n-+=+-=\n").unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].cells.len(), 44);
+        assert_loop(&tracks[0]);
+
+        let block = vec!["=".repeat(30); 30].join("\n").replacen('=', "S", 1);
+        let tracks = trace(&block).unwrap();
+        assert_eq!(tracks[0].cells.len(), 900);
+        assert_loop(&tracks[0]);
+    }
+
+    #[test]
+    fn traces_separate_tracks() {
+        let tracks = trace("S==+  S=+\n=  =  = =\n+==-  =+=\n").unwrap();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!((tracks[0].start, tracks[0].cells.len()), ((0, 0), 10));
+        assert_eq!((tracks[1].start, tracks[1].cells.len()), ((6, 0), 8));
+        tracks.iter().for_each(assert_loop);
+        // Clockwise from `S`
+        assert_eq!(tracks[0].cells[0], (1, 0));
+    }
+
+    #[test]
+    fn rejects_tracks_that_are_not_loops() {
+        let error = |map: &str| match trace(map) {
+            Err(Error::InvalidInput(e)) => e,
+            other => panic!("{map:?} traced to {:?}", other.map(|t| t.len())),
+        };
+        // Two ways round between the top and bottom right, so one loop leaves a piece out
+        assert!(error("S==+=\n=  ==\n=+=+=\n").contains("can't be split"));
+        // An odd square block can't be walked round once
+        assert!(error(&["+++"; 3].join("\n").replacen('+', "S", 1)).contains("can't be split"));
+        assert!(error("S==+\n=  =\n+==-\n  =\n").contains("dead end"));
+        assert!(error("S=\n").contains("broken"));
+        assert!(error("S==+ ==\n=  = ==\n+==-\n").contains("does not lead back"));
+        assert!(error("+==+\n=  =\n+==-\n").contains("no S"));
+    }
+
+    #[test]
+    fn traces_generated_tracks() {
+        let mut rng = Rng::new(7);
+        for size in 1..=12 {
+            for _ in 0..20 {
+                let notes = generator(2024, 7).unwrap()(&mut rng, size);
+                let day = Day07::from_notes(notes.as_bytes()).unwrap();
+                day.tracks().iter().for_each(assert_loop);
+            }
+        }
+    }
+
+    #[test]
+    fn scorer_matches_simulation() {
+        let day = Day07::from_notes(TRACK.as_bytes()).unwrap();
+        let track = day.track();
+        assert_eq!(track.len(), 12);
+        for plan in ["+", "+-", "=+-", "+-=+", "++-=-=+", "+++++---==="] {
+            let plan = commands(plan);
+            for laps in [0, 1, 2, 7, 10, 33] {
+                assert_eq!(
+                    RaceScorer::new(track, plan.len(), laps).score(&plan),
+                    simulate(track, &plan, laps),
+                    "plan {plan:?} over {laps} laps"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scorer_scales_to_huge_lap_counts() {
+        // A track of only S leaves every step to the plan, so always going up makes the total
+        // the sum of 10 + k over steps 1 to n
+        let n = 1_000_000_000i128;
+        let scorer = RaceScorer::new(&[Command::StartEnd], 1, n as usize);
+        assert_eq!(scorer.score(&[Command::Inc]), 10 * n + n * (n + 1) / 2);
+        assert_eq!(scorer.score(&[Command::Stay]), 10 * n);
+    }
+}
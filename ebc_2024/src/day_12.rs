@@ -1,12 +1,45 @@
 #[allow(unused_imports)]
 use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt, Point2D};
-use std::collections::VecDeque;
 
 #[derive(Debug)]
-struct Catapult {
-    id: usize,
-    point: Point2D<isize>,
-    hits: HashMap<Point2D<isize>, (usize, usize)>,
+pub struct Catapult {
+    pub id: usize,
+    pub point: Point2D<isize>,
+}
+
+/// A meteor's position in the notes and how far it falls each step.  Like the catapults' points
+/// `y` grows downwards, so a meteor given as `x y` in the notes starts at `(x, -y)`.
+#[derive(Copy, Clone, Debug)]
+pub struct Meteor {
+    pub point: Point2D<isize>,
+    pub velocity: Point2D<isize>,
+}
+
+impl Meteor {
+    pub fn at(&self, time: isize) -> Point2D<isize> {
+        Point2D::new(
+            self.point.x + self.velocity.x * time,
+            self.point.y + self.velocity.y * time,
+        )
+    }
+}
+
+/// Where and when a shot meets a meteor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Intercept {
+    pub catapult: usize,
+    pub power: usize,
+    /// Steps the meteor has fallen when it is hit
+    pub time: usize,
+    /// Steps waited before firing
+    pub delay: usize,
+    pub point: Point2D<isize>,
+}
+
+impl Intercept {
+    pub fn ranking(&self) -> usize {
+        self.catapult * self.power
+    }
 }
 
 impl Catapult {
@@ -14,37 +47,78 @@ impl Catapult {
         Self {
             id: (c as u8 - b'A' + 1) as usize,
             point: Point2D::new(x, y),
-            hits: HashMap::default(),
         }
     }
 
-    fn calc_hits(&mut self, max_power: usize) {
-        let mut p = self.point;
-        'power: for power in 1..max_power {
-            p.x += 1;
-            p.y -= 1;
-            self.hits.insert(p, (power, power));
-            // Step to the right
-            for t in 1..power {
-                let p = Point2D::new(p.x + t as isize, p.y);
-                if power + t > max_power {
-                    continue 'power;
+    /// Earliest hit on `meteor`, optionally only firing after exactly `delay` steps.
+    ///
+    /// A shot's x distance from the catapult is the time since it was fired, and its height is
+    /// that time while rising, the power while flying flat (from power to 2 * power steps), and
+    /// 3 * power minus the time while falling.  The rising part is the same as the start of the
+    /// flat part with a smaller power, so for each of flat and falling every condition on the
+    /// hit is linear in the meteor's time, falling also needing the power to be whole.  That
+    /// gives a range of times to take the first (whole) one from.
+    pub fn intercept(&self, meteor: &Meteor, delay: Option<usize>) -> Option<Intercept> {
+        // Distance and height from the catapult at time t, each as (per step, at time 0)
+        let dx = (meteor.velocity.x, meteor.point.x - self.point.x);
+        let up = (-meteor.velocity.y, self.point.y - meteor.point.y);
+        let ground = (-meteor.velocity.y, -meteor.point.y);
+        let sum = |a: (isize, isize), b: (isize, isize), k: isize| (a.0 + k * b.0, a.1 + k * b.1);
+        // Time waited before firing: t - dx
+        let waited = sum((1, 0), dx, -1);
+
+        let mut common = vec![(dx.0, dx.1 - 1), ground, waited];
+        if let Some(delay) = delay {
+            let delay = delay as isize;
+            common.push((waited.0, waited.1 - delay));
+            common.push((-waited.0, delay - waited.1));
+        }
+
+        let mut best: Option<Intercept> = None;
+        for falling in [false, true] {
+            // Every (a, b) here needs a * t + b >= 0
+            let mut conditions = common.clone();
+            let (power, divisor) = if falling {
+                // power = (up + dx) / 3, with dx >= 2 * power and power >= 1
+                conditions.push(sum(dx, up, -2));
+                let power = sum(up, dx, 1);
+                conditions.push((power.0, power.1 - 3));
+                (power, 3)
+            } else {
+                // power = up, with up <= dx <= 2 * up and power >= 1
+                conditions.push(sum(dx, up, -1));
+                conditions.push((2 * up.0 - dx.0, 2 * up.1 - dx.1));
+                conditions.push((up.0, up.1 - 1));
+                (up, 1)
+            };
+
+            let (mut lo, mut hi) = (1isize, isize::MAX);
+            for (a, b) in conditions {
+                match a.signum() {
+                    1 => lo = lo.max((-b).div_euclid(a) + ((-b).rem_euclid(a) != 0) as isize),
+                    -1 => hi = hi.min(b.div_euclid(-a)),
+                    _ if b < 0 => hi = lo - 1,
+                    _ => {}
                 }
-                self.hits.insert(p, (power, power + t));
             }
-            // Step diag down
-            let mut p: Point2D<isize> = Point2D::new(p.x + power as isize, p.y);
-            let mut t = 2 * power;
-            while p.y <= 0 {
-                t += 1;
-                p.y += 1;
-                p.x += 1;
-                if t > max_power {
-                    continue 'power;
-                }
-                self.hits.insert(p, (power, t));
+            let Some(time) = (lo..=hi.min(lo.saturating_add(divisor - 1)))
+                .find(|t| (power.0 * t + power.1).rem_euclid(divisor) == 0)
+            else {
+                continue;
+            };
+
+            let intercept = Intercept {
+                catapult: self.id,
+                power: ((power.0 * time + power.1) / divisor) as usize,
+                time: time as usize,
+                delay: (waited.0 * time + waited.1) as usize,
+                point: meteor.at(time),
+            };
+            if best.is_none_or(|best| intercept.time < best.time) {
+                best = Some(intercept);
             }
         }
+        best
     }
 
     fn can_hit_target(&self, target: &Point2D<isize>) -> Option<usize> {
@@ -65,9 +139,9 @@ impl Catapult {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Target {
-    point: Point2D<isize>,
-    hp: usize,
+pub struct Target {
+    pub point: Point2D<isize>,
+    pub hp: usize,
 }
 
 impl Target {
@@ -79,11 +153,40 @@ impl Target {
     }
 }
 
+/// One catapult shot at a target.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shot {
+    pub catapult: usize,
+    pub power: usize,
+    pub target: Point2D<isize>,
+    /// Hits the target can still take afterwards, 0 once it is destroyed
+    pub hp: usize,
+}
+
+impl Shot {
+    pub fn ranking(&self) -> usize {
+        self.catapult * self.power
+    }
+}
+
+/// Every shot needed to destroy all the targets, in the order they are fired.
+#[derive(Clone, Debug, Default)]
+pub struct ShotPlan {
+    pub shots: Vec<Shot>,
+}
+
+impl ShotPlan {
+    pub fn ranking(&self) -> usize {
+        self.shots.iter().map(Shot::ranking).sum()
+    }
+}
+
 #[derive(Default)]
 pub struct Day12 {
+    map: Vec<Vec<char>>,
     catapults: Vec<Catapult>,
     targets: Vec<Target>,
-    meteors: Vec<Point2D<isize>>,
+    meteors: Vec<Meteor>,
 }
 
 impl Day12 {
@@ -91,127 +194,549 @@ impl Day12 {
         Self::default()
     }
 
-    fn find_ranking(&self) -> usize {
-        let mut rankings = 0;
-        let mut targets: VecDeque<Target> = self.targets.iter().copied().collect();
-        let mut remaining: VecDeque<Target> = VecDeque::new();
-        while !targets.is_empty() {
-            'find_target: while let Some(mut target) = targets.pop_front() {
-                if remaining
-                    .iter()
-                    .any(|t| t.hp > 0 && t.point.x == target.point.x && t.point.y < target.point.y)
-                {
-                    // Can't target if there is something above it
-                    remaining.push_back(target);
-                    continue;
-                }
-                for catapult in self.catapults.iter() {
-                    if let Some(power) = catapult.can_hit_target(&target.point) {
-                        rankings += power * catapult.id;
-                        target.hp -= 1;
-                        if target.hp > 0 {
-                            remaining.push_back(target);
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        if lines.is_empty() {
+            return Err(Error::InvalidInput("No notes".into()));
+        }
+        if lines[0].starts_with('.') {
+            for (y, line) in lines.iter().enumerate() {
+                day.map.push(line.chars().collect());
+                for (x, c) in line.chars().enumerate() {
+                    match c {
+                        'T' => day.targets.push(Target::new(1, x, y)),
+                        'H' => day.targets.push(Target::new(2, x, y)),
+                        'A'..='Z' => day.catapults.push(Catapult::new(c, x as isize, y as isize)),
+                        '.' | '=' => {}
+                        _ => {
+                            return Err(Error::InvalidInput(format!(
+                                "Unknown {c:?} on the map at ({x}, {y})"
+                            )))
                         }
-                        remaining.append(&mut targets);
-                        break 'find_target;
                     }
                 }
-
-                remaining.push_back(target);
             }
-            std::mem::swap(&mut targets, &mut remaining);
+        } else {
+            // Catapults are `<letter> <x> <height>` lines, defaulting to A, B and C stacked at
+            // x 0.  Meteors are `<x> <y>` lines, optionally followed by `<dx> <dy>` for how they
+            // fall each step, defaulting to one left and one down.
+            for line in lines.iter() {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                match parts.as_slice() {
+                    [name, x, height]
+                        if name.len() == 1 && name.as_bytes()[0].is_ascii_uppercase() =>
+                    {
+                        day.catapults.push(Catapult::new(
+                            name.as_bytes()[0] as char,
+                            x.parse()?,
+                            -height.parse::<isize>()?,
+                        ));
+                    }
+                    [x, y] | [x, y, _, _] => {
+                        let velocity = match parts.as_slice() {
+                            [_, _, dx, dy] => Point2D::new(dx.parse()?, -dy.parse::<isize>()?),
+                            _ => Point2D::new(-1, 1),
+                        };
+                        day.meteors.push(Meteor {
+                            point: Point2D::new(x.parse()?, -y.parse::<isize>()?),
+                            velocity,
+                        });
+                    }
+                    _ => return Err(Error::InvalidInput(line.into())),
+                }
+            }
+            if day.catapults.is_empty() {
+                day.catapults.push(Catapult::new('A', 0, 0));
+                day.catapults.push(Catapult::new('B', 0, -1));
+                day.catapults.push(Catapult::new('C', 0, -2));
+            }
         }
-        rankings
+        Ok(day)
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.find_ranking().into())
+    pub fn catapults(&self) -> &[Catapult] {
+        &self.catapults
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.find_ranking().into())
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn meteors(&self) -> &[Meteor] {
+        &self.meteors
+    }
+
+    /// Shoots down the targets one hit at a time, always taking the first target in reading
+    /// order which has nothing stacked above it and which a catapult can hit, with the lowest
+    /// ranking catapult when several can.
+    pub fn shot_plan(&self) -> Result<ShotPlan, Error> {
+        let mut hp: Vec<usize> = self.targets.iter().map(|t| t.hp).collect();
+        let mut plan = ShotPlan::default();
+        while hp.iter().any(|hp| *hp > 0) {
+            let exposed = |idx: usize| {
+                let point = self.targets[idx].point;
+                hp[idx] > 0
+                    && !self.targets.iter().zip(hp.iter()).any(|(above, hp)| {
+                        *hp > 0 && above.point.x == point.x && above.point.y < point.y
+                    })
+            };
+            let shot = (0..self.targets.len())
+                .filter(|idx| exposed(*idx))
+                .find_map(|idx| {
+                    let target = self.targets[idx].point;
+                    self.catapults
+                        .iter()
+                        .filter_map(|catapult| {
+                            let power = catapult.can_hit_target(&target)?;
+                            Some((idx, catapult.id, power))
+                        })
+                        .min_by_key(|(_, catapult, power)| catapult * power)
+                });
+            let Some((idx, catapult, power)) = shot else {
+                let stuck = (0..self.targets.len()).find(|idx| exposed(*idx)).unwrap();
+                let point = self.targets[stuck].point;
+                return Err(Error::InvalidInput(format!(
+                    "No catapult can hit the target at ({}, {})",
+                    point.x, point.y
+                )));
+            };
+
+            hp[idx] -= 1;
+            plan.shots.push(Shot {
+                catapult,
+                power,
+                target: self.targets[idx].point,
+                hp: hp[idx],
+            });
+        }
+        Ok(plan)
+    }
+
+    /// The map once the first `shots` shots of `plan` have been fired.  Targets still standing
+    /// show their hits left (`H` for 2, `T` for 1) and destroyed ones the number of the shot
+    /// which destroyed them, followed by what each of those shots did.
+    pub fn render_plan(&self, plan: &ShotPlan, shots: usize) -> String {
+        let label = |number: usize| match number {
+            n @ 1..=9 => (b'0' + n as u8) as char,
+            n @ 10..=35 => (b'a' + (n - 10) as u8) as char,
+            _ => '*',
+        };
+        let name = |id: usize| (b'A' + id as u8 - 1) as char;
+
+        let mut map = self.map.clone();
+        for target in self.targets.iter() {
+            map[target.point.y as usize][target.point.x as usize] = match target.hp {
+                2 => 'H',
+                _ => 'T',
+            };
+        }
+        for (number, shot) in plan.shots.iter().take(shots).enumerate() {
+            map[shot.target.y as usize][shot.target.x as usize] = match shot.hp {
+                0 => label(number + 1),
+                1 => 'T',
+                _ => 'H',
+            };
+        }
+
+        let mut rendered = String::new();
+        for row in map.iter() {
+            rendered.extend(row.iter());
+            rendered.push('\n');
+        }
+        let fired = &plan.shots[..shots.min(plan.shots.len())];
+        rendered.push_str(&format!(
+            "Ranking {} after {} of {} shots\n",
+            fired.iter().map(Shot::ranking).sum::<usize>(),
+            fired.len(),
+            plan.shots.len()
+        ));
+        for (number, shot) in fired.iter().enumerate() {
+            rendered.push_str(&format!(
+                "{:>3}: {} power {} at ({}, {}), {}\n",
+                number + 1,
+                name(shot.catapult),
+                shot.power,
+                shot.target.x,
+                shot.target.y,
+                match shot.hp {
+                    0 => "destroyed".to_string(),
+                    hp => format!("{hp} hp left"),
+                }
+            ));
+        }
+        rendered
+    }
+
+    pub fn part1(&mut self) -> Result<usize, Error> {
+        Ok(self.shot_plan()?.ranking())
+    }
+
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.shot_plan()?.ranking())
+    }
+
+    /// The earliest hit on a meteor from any catapult, the lowest ranking one when several
+    /// catapults hit it at the same time.
+    pub fn best_intercept(&self, meteor: &Meteor, delay: Option<usize>) -> Option<Intercept> {
+        self.catapults
+            .iter()
+            .filter_map(|catapult| catapult.intercept(meteor, delay))
+            .min_by_key(|intercept| (intercept.time, intercept.ranking()))
+    }
+
+    pub fn part3(&mut self) -> Result<usize, Error> {
         let mut ranking = 0;
+        for meteor in self.meteors.iter() {
+            let intercept = self.best_intercept(meteor, None).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Meteor at {} {} can't be hit",
+                    meteor.point.x, -meteor.point.y
+                ))
+            })?;
+            ranking += intercept.ranking();
+        }
+        Ok(ranking)
+    }
 
+    // Reference implementation for stress testing: every point each catapult's shots pass through
+    // with the power and steps since firing, then each meteor stepped down until a shot can be
+    // waiting for it there.
+    pub(crate) fn part3_simulate(&mut self) -> Result<usize, Error> {
+        if self
+            .meteors
+            .iter()
+            .any(|m| m.velocity != Point2D::new(-1, 1))
+        {
+            return Err(Error::InvalidInput(
+                "Only meteors falling one left and one down can be simulated".into(),
+            ));
+        }
         let max_power = self
             .meteors
             .iter()
-            .map(|p| p.y)
+            .map(|m| m.point.y)
             .min()
-            .unwrap()
+            .unwrap_or(0)
             .unsigned_abs();
-        for catapult in self.catapults.iter_mut() {
-            catapult.calc_hits(max_power);
-        }
 
-        let mut actual_max_power = usize::MIN;
+        let hits: Vec<HashMap<Point2D<isize>, (usize, usize)>> = self
+            .catapults
+            .iter()
+            .map(|catapult| {
+                let mut hits = HashMap::default();
+                let mut p = catapult.point;
+                'power: for power in 1..=max_power {
+                    // Up
+                    p.x += 1;
+                    p.y -= 1;
+                    hits.insert(p, (power, power));
+                    // Across
+                    for t in 1..=power {
+                        if power + t > max_power {
+                            continue 'power;
+                        }
+                        hits.insert(Point2D::new(p.x + t as isize, p.y), (power, power + t));
+                    }
+                    // Down
+                    let mut p = Point2D::new(p.x + power as isize, p.y);
+                    let mut t = 2 * power;
+                    while p.y < 0 && t < max_power {
+                        t += 1;
+                        p.x += 1;
+                        p.y += 1;
+                        hits.insert(p, (power, t));
+                    }
+                }
+                hits
+            })
+            .collect();
 
+        let mut ranking = 0;
         for meteor in self.meteors.iter() {
-            let mut min_ranking = usize::MAX;
-            let mut meteor = *meteor;
+            let mut point = meteor.point;
             let mut t = 0;
-            while meteor.y < 0 && min_ranking == usize::MAX {
-                meteor.x -= 1;
-                meteor.y += 1;
+            let best = loop {
+                if point.y >= 0 {
+                    return Err(Error::InvalidInput(format!(
+                        "Meteor at {} {} can't be hit",
+                        meteor.point.x, -meteor.point.y
+                    )));
+                }
+                point.x -= 1;
+                point.y += 1;
                 t += 1;
-                for catapult in self.catapults.iter() {
-                    if let Some((power, time)) = catapult.hits.get(&meteor) {
-                        if *time <= t {
-                            min_ranking = min_ranking.min(catapult.id * power);
-                            actual_max_power = actual_max_power.max(*time);
-                        }
-                    }
+                let best = self
+                    .catapults
+                    .iter()
+                    .zip(hits.iter())
+                    .filter_map(|(catapult, hits)| match hits.get(&point) {
+                        Some((power, time)) if *time <= t => Some(catapult.id * power),
+                        _ => None,
+                    })
+                    .min();
+                if let Some(best) = best {
+                    break best;
                 }
-            }
-
-            assert_ne!(min_ranking, usize::MAX);
-            ranking += min_ranking;
+            };
+            ranking += best;
         }
-
-        Ok(ranking.into())
+        Ok(ranking)
     }
 }
 
 impl helper::Runner for Day12 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        if lines[0].starts_with('.') {
-            for (y, line) in lines.iter().enumerate() {
-                for (x, c) in line.chars().enumerate() {
-                    match c {
-                        'A' | 'B' | 'C' => self
-                            .catapults
-                            .push(Catapult::new(c, x as isize, y as isize)),
-                        'T' => self.targets.push(Target::new(1, x, y)),
-                        'H' => self.targets.push(Target::new(2, x, y)),
-                        _ => assert!(matches!(c, '.' | '=')),
-                    }
-                }
+        *self = Self::from_notes(file)?;
+        Ok(())
+    }
+
+    fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
+        match part {
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
+            _ => Err(Error::Skipped),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{generator, Rng};
+
+    const EXAMPLE: &str =
+        ".............\n.C...........\n.B......T....\n.A......T.T..\n=============\n";
+    const EXAMPLE_2: &str =
+        ".............\n.C...........\n.B......H....\n.A......T.H..\n=============\n";
+    const METEORS: &str = "6 5\n6 7\n10 5\n";
+
+    fn day(notes: &str) -> Day12 {
+        Day12::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    // Where a shot is `flight` steps after being fired
+    fn shot(catapult: &Catapult, power: isize, flight: isize) -> Point2D<isize> {
+        let height = match flight {
+            t if t <= power => t,
+            t if t <= 2 * power => power,
+            t => 3 * power - t,
+        };
+        Point2D::new(catapult.point.x + flight, catapult.point.y - height)
+    }
+
+    // The earliest time any shot meets the meteor above ground and the lowest ranking of the shots
+    // which do, trying every flight and power
+    fn first_hit(day: &Day12, meteor: &Meteor, limit: isize) -> Option<(usize, usize)> {
+        (1..=limit).find_map(|time| {
+            let at = meteor.at(time);
+            if at.y > 0 {
+                return None;
             }
-        } else {
-            self.catapults.push(Catapult::new('A', 0, 0));
-            self.catapults.push(Catapult::new('B', 0, -1));
-            self.catapults.push(Catapult::new('C', 0, -2));
+            day.catapults
+                .iter()
+                .flat_map(|catapult| {
+                    (1..=time).flat_map(move |flight| {
+                        (1..=flight).filter_map(move |power| {
+                            (shot(catapult, power, flight) == at)
+                                .then_some(catapult.id * power as usize)
+                        })
+                    })
+                })
+                .min()
+                .map(|ranking| (time as usize, ranking))
+        })
+    }
 
-            for line in lines.iter() {
-                if let Some((x, y)) = line.split_once(' ') {
-                    self.meteors.push(Point2D::new(x.parse()?, -y.parse()?));
-                } else {
-                    return Err(Error::InvalidInput(line.into()));
+    fn check_intercepts(day: &Day12) {
+        for meteor in day.meteors.iter() {
+            let intercept = day.best_intercept(meteor, None);
+            let limit = intercept.map_or(40, |intercept| intercept.time as isize);
+            assert_eq!(
+                intercept.map(|intercept| (intercept.time, intercept.ranking())),
+                first_hit(day, meteor, limit),
+                "{meteor:?}"
+            );
+            if let Some(intercept) = intercept {
+                let catapult = day
+                    .catapults
+                    .iter()
+                    .find(|c| c.id == intercept.catapult)
+                    .unwrap();
+                let flight = (intercept.time - intercept.delay) as isize;
+                let at = shot(catapult, intercept.power as isize, flight);
+                assert_eq!(at, meteor.at(intercept.time as isize));
+                assert_eq!(at, intercept.point);
+            }
+        }
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(day(EXAMPLE).part1().unwrap(), 13);
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(day(EXAMPLE_2).part2().unwrap(), 22);
+    }
+
+    #[test]
+    fn part3_example() {
+        assert_eq!(day(METEORS).part3().unwrap(), 11);
+        assert_eq!(day(METEORS).part3_simulate().unwrap(), 11);
+        check_intercepts(&day(METEORS));
+    }
+
+    #[test]
+    fn intercepts_are_the_earliest_hits() {
+        // Other catapults and meteors falling in other directions
+        check_intercepts(&day(
+            "A 0 0\nD 2 4\n12 9\n20 3 -2 -1\n5 15 0 -1\n30 2 -1 0\n",
+        ));
+
+        let generate = generator(2024, 12).unwrap();
+        let mut rng = Rng::new(12);
+        for _ in 0..100 {
+            let day = day(&generate(&mut rng, 4));
+            check_intercepts(&day);
+        }
+    }
+
+    #[test]
+    fn intercepts_after_a_fixed_delay() {
+        let day = day(METEORS);
+        for meteor in day.meteors.iter() {
+            for delay in 0..4 {
+                if let Some(intercept) = day
+                    .catapults
+                    .iter()
+                    .find_map(|catapult| catapult.intercept(meteor, Some(delay)))
+                {
+                    assert_eq!(intercept.delay, delay);
+                    let catapult = day
+                        .catapults
+                        .iter()
+                        .find(|c| c.id == intercept.catapult)
+                        .unwrap();
+                    let flight = (intercept.time - delay) as isize;
+                    assert_eq!(
+                        shot(catapult, intercept.power as isize, flight),
+                        meteor.at(intercept.time as isize)
+                    );
                 }
             }
         }
-        Ok(())
     }
 
-    fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
-        match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
-            _ => Err(Error::Skipped),
+    #[test]
+    fn meteors_out_of_reach() {
+        // Already behind the catapults and heading further away
+        let mut away = day("-5 5\n");
+        let meteor = away.meteors[0];
+        assert!(away
+            .catapults
+            .iter()
+            .all(|catapult| catapult.intercept(&meteor, None).is_none()));
+        assert!(away.best_intercept(&meteor, None).is_none());
+        assert!(matches!(away.part3(), Err(Error::InvalidInput(_))));
+
+        // Nothing stacked on the target, but no catapult lines up with it
+        assert!(matches!(
+            day(".......\n.A...T.\n=======\n").shot_plan(),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn ties_go_to_the_lowest_ranking_catapult() {
+        // A with power 2 and D with power 1 both hit the target
+        let day = day(".D......\n........\n........\n.A.....T\n========\n");
+        let plan = day.shot_plan().unwrap();
+        assert_eq!(plan.shots.len(), 1);
+        assert_eq!((plan.shots[0].catapult, plan.shots[0].power), (1, 2));
+        assert_eq!(plan.ranking(), 2);
+
+        // A and D hitting a meteor at the same time
+        let day = self::day("A 0 0\nD 0 3\n6 5\n");
+        let mut ties = 0;
+        for x in 1..30 {
+            for y in 1..30 {
+                let meteor = Meteor {
+                    point: Point2D::new(x, -y),
+                    velocity: Point2D::new(-1, 1),
+                };
+                let hits: Vec<Intercept> = day
+                    .catapults
+                    .iter()
+                    .filter_map(|catapult| catapult.intercept(&meteor, None))
+                    .collect();
+                let Some(best) = day.best_intercept(&meteor, None) else {
+                    assert!(hits.is_empty());
+                    continue;
+                };
+                let tied: Vec<&Intercept> =
+                    hits.iter().filter(|hit| hit.time == best.time).collect();
+                if tied.len() > 1 {
+                    ties += 1;
+                }
+                assert_eq!(
+                    Some(best.ranking()),
+                    tied.iter().map(|hit| hit.ranking()).min()
+                );
+            }
         }
+        assert!(ties > 0);
+    }
+
+    #[test]
+    fn rendered_plan() {
+        let day = day(EXAMPLE);
+        let plan = day.shot_plan().unwrap();
+        assert_eq!(
+            day.render_plan(&plan, plan.shots.len()),
+            ".............\n\
+             .C...........\n\
+             .B......1....\n\
+             .A......2.3..\n\
+             =============\n\
+             Ranking 13 after 3 of 3 shots\n  \
+             1: C power 2 at (8, 2), destroyed\n  \
+             2: B power 2 at (8, 3), destroyed\n  \
+             3: A power 3 at (10, 3), destroyed\n"
+        );
+        assert_eq!(
+            day.render_plan(&plan, 2),
+            ".............\n\
+             .C...........\n\
+             .B......1....\n\
+             .A......2.T..\n\
+             =============\n\
+             Ranking 10 after 2 of 3 shots\n  \
+             1: C power 2 at (8, 2), destroyed\n  \
+             2: B power 2 at (8, 3), destroyed\n"
+        );
+    }
+
+    #[test]
+    fn any_other_capital_is_a_catapult() {
+        let day = day(".....\n.D...\n.A..T\n=====\n");
+        let ids: Vec<usize> = day.catapults.iter().map(|catapult| catapult.id).collect();
+        assert_eq!(ids, [4, 1]);
+        assert_eq!(day.targets.len(), 1);
+
+        assert!(matches!(
+            Day12::from_notes(b".....\n.A.x.\n=====\n"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn empty_notes() {
+        assert!(matches!(
+            Day12::from_notes(b""),
+            Err(Error::InvalidInput(_))
+        ));
     }
 }
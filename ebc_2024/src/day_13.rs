@@ -1,19 +1,41 @@
 #[allow(unused_imports)]
-use helper::{print, println, Dijkstra, Error, HashMap, Lines, LinesOpt, Point2D};
-
-struct Tile {
-    point: Point2D<isize>,
-    level: i8,
-    is_start: bool,
-    is_end: bool,
-    neighbors: Vec<(usize, usize)>,
+use helper::{print, println, Error, HashMap, Lines, LinesOpt};
+
+use crate::grid::{GridGraph, Path};
+
+/// How long moving around the maze takes.
+#[derive(Copy, Clone, Debug)]
+pub struct Rules {
+    /// Levels a platform can be set to, from 1 to 10, going past the highest wraps back to 0
+    pub levels: u32,
+    /// Time for a step to an adjacent platform, on top of changing levels, at least 1
+    pub step: u32,
+    /// Time to go between two pads with the same letter, at least 1
+    pub teleport: u32,
+}
+
+impl Rules {
+    /// Ten levels and a second for every step, level change and teleport.
+    pub const QUEST: Self = Self {
+        levels: 10,
+        step: 1,
+        teleport: 1,
+    };
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::QUEST
+    }
 }
 
 #[derive(Default)]
 pub struct Day13 {
-    tiles: Vec<Tile>,
-    start: usize,
-    end: usize,
+    rows: Vec<Vec<char>>,
+    rules: Rules,
+    maze: GridGraph,
+    starts: Vec<usize>,
+    ends: Vec<usize>,
 }
 
 impl Day13 {
@@ -21,111 +43,193 @@ impl Day13 {
         Self::default()
     }
 
-    fn map<F>(&mut self, start: usize, is_end: F) -> usize
-    where
-        F: Fn(&Tile) -> bool,
-    {
-        Dijkstra::find_first(start, |idx| {
-            self.tiles[idx]
-                .neighbors
-                .iter()
-                .copied()
-                .map(|(to, time)| (time, to, is_end(&self.tiles[to])))
-        })
-        .unwrap()
-        .0
+    /// Platforms are digits giving their level, with `S` and `E` at level 0.  Lowercase letters
+    /// are teleport pads at level 0, linked to every other pad with the same letter.
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        day.rows = lines.iter().map(|line| line.chars().collect()).collect();
+        day.build()?;
+        if day.starts.is_empty() || day.ends.is_empty() {
+            return Err(Error::InvalidInput("Maze needs an S and an E".into()));
+        }
+        Ok(day)
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Result<Self, Error> {
+        if !(1..=10).contains(&rules.levels) {
+            return Err(Error::InvalidInput(format!(
+                "Platforms are single digits, so {} levels won't do",
+                rules.levels
+            )));
+        }
+        // The maze only takes moves that cost something
+        if rules.step < 1 || rules.teleport < 1 {
+            return Err(Error::InvalidInput(format!(
+                "Steps and teleports take at least a second, not {} and {}",
+                rules.step, rules.teleport
+            )));
+        }
+        self.rules = rules;
+        self.build()?;
+        Ok(self)
+    }
+
+    fn build(&mut self) -> Result<(), Error> {
+        let Rules {
+            levels,
+            step,
+            teleport,
+        } = self.rules;
+        let level = |c: char| match c {
+            'S' | 'E' | 'a'..='z' => Some(0),
+            c => c.to_digit(10),
+        };
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if level(*c).is_some_and(|level| level >= levels) {
+                    return Err(Error::InvalidInput(format!(
+                        "Platform at ({x}, {y}) is above the highest of {levels} levels"
+                    )));
+                }
+            }
+        }
+        self.maze = GridGraph::new(&self.rows, |from, to| {
+            let change = level(from)?.abs_diff(level(to)?);
+            Some(change.min(levels - change) + step)
+        });
+        self.starts = self.maze.find('S');
+        self.ends = self.maze.find('E');
+
+        let mut pads: HashMap<char, Vec<usize>> = HashMap::default();
+        for cell in 0..self.maze.len() {
+            if self.maze.cell(cell).is_ascii_lowercase() {
+                pads.entry(self.maze.cell(cell)).or_default().push(cell);
+            }
+        }
+        for (label, cells) in pads.iter() {
+            if cells.len() < 2 {
+                return Err(Error::InvalidInput(format!("Pad {label} has no other end")));
+            }
+            for from in cells.iter() {
+                for to in cells.iter().filter(|to| *to != from) {
+                    self.maze.link(*from, *to, teleport);
+                }
+            }
+        }
+        Ok(())
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.map(self.start, |t| t.is_end).into())
+    pub fn rules(&self) -> Rules {
+        self.rules
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.map(self.start, |t| t.is_end).into())
+    pub fn maze(&self) -> &GridGraph {
+        &self.maze
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self.map(self.end, |t| t.is_start).into())
+    /// The quickest way from any S to any E.
+    pub fn quickest_path(&self) -> Result<Path, Error> {
+        self.maze
+            .nearest(&self.starts, |cell| self.ends.contains(&cell))
+            .ok_or_else(|| Error::InvalidInput("E can't be reached".into()))
+    }
+
+    /// Every S in reading order with the least time it takes to get from there to an E, if it
+    /// can.  Moves cost the same both ways, so this is one search out from all the Es.
+    pub fn start_times(&self) -> Vec<((usize, usize), Option<usize>)> {
+        let paths = self.maze.paths_from(&self.ends);
+        self.starts
+            .iter()
+            .map(|start| (self.maze.xy(*start), paths.cost(*start)))
+            .collect()
+    }
+
+    pub fn part1(&mut self) -> Result<usize, Error> {
+        Ok(self.quickest_path()?.cost)
+    }
+
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.quickest_path()?.cost)
+    }
+
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        Ok(self.quickest_path()?.cost)
     }
 }
 
 impl helper::Runner for Day13 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for (y, line) in lines.iter().enumerate() {
-            let y = y as isize;
-            for (x, c) in line.chars().enumerate() {
-                let x = x as isize;
-                let p = Point2D::new(x, y);
-                match c {
-                    'S' => {
-                        self.start = self.tiles.len();
-                        self.tiles.push(Tile {
-                            point: p,
-                            level: 0,
-                            is_start: true,
-                            is_end: false,
-                            neighbors: Vec::new(),
-                        });
-                    }
-                    'E' => {
-                        self.end = self.tiles.len();
-                        self.tiles.push(Tile {
-                            point: p,
-                            level: 0,
-                            is_start: false,
-                            is_end: true,
-                            neighbors: Vec::new(),
-                        });
-                    }
-                    c if c.is_ascii_digit() => {
-                        self.tiles.push(Tile {
-                            point: p,
-                            level: (c as u8 - b'0') as i8,
-                            is_start: false,
-                            is_end: false,
-                            neighbors: Vec::new(),
-                        });
-                    }
-                    _ => {}
-                }
-            }
-        }
+        *self = Self::from_notes(file)?;
+        Ok(())
+    }
 
-        let mut tiles = HashMap::default();
-        for (idx, tile) in self.tiles.iter().enumerate() {
-            tiles.insert(tile.point, idx);
+    fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
+        match part {
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
+            _ => Err(Error::Skipped),
         }
+    }
+}
 
-        for from in 0..self.tiles.len() {
-            let from_level = self.tiles[from].level;
-            macro_rules! link {
-                ($x:expr, $y:expr) => {{
-                    if let Some(to) = tiles.get(&Point2D::new($x, $y)) {
-                        let to_level = self.tiles[*to].level;
-                        let time = from_level.abs_diff(to_level);
-                        let time = time.min(10 - time) + 1;
-                        self.tiles[from].neighbors.push((*to, time as usize));
-                    }
-                }};
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#######\n#6769##\nS50505E\n#97434#\n#######\n";
 
-            let x = self.tiles[from].point.x;
-            let y = self.tiles[from].point.y;
-            link!(x - 1, y);
-            link!(x + 1, y);
-            link!(x, y - 1);
-            link!(x, y + 1);
+    #[test]
+    fn part1_example() {
+        let mut day = Day13::from_notes(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(day.part1().unwrap(), 28);
+    }
+
+    #[test]
+    fn levels_must_fit_the_platforms() {
+        let rules = |levels| Rules {
+            levels,
+            ..Rules::QUEST
+        };
+        for levels in [0, 5, 9, 11] {
+            let day = Day13::from_notes(EXAMPLE.as_bytes()).unwrap();
+            assert!(matches!(
+                day.with_rules(rules(levels)),
+                Err(Error::InvalidInput(_))
+            ));
         }
-        Ok(())
+        let day = Day13::from_notes(b"S12E\n").unwrap();
+        assert!(day.with_rules(rules(3)).is_ok());
     }
 
-    fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
-        match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
-            _ => Err(Error::Skipped),
+    #[test]
+    fn moves_must_take_time() {
+        for (notes, rules) in [
+            (
+                "S11E\n",
+                Rules {
+                    step: 0,
+                    ..Rules::QUEST
+                },
+            ),
+            (
+                "Sa#aE\n",
+                Rules {
+                    teleport: 0,
+                    ..Rules::QUEST
+                },
+            ),
+        ] {
+            let day = Day13::from_notes(notes.as_bytes()).unwrap();
+            assert!(matches!(day.with_rules(rules), Err(Error::InvalidInput(_))));
         }
+        let day = Day13::from_notes(b"Sa#aE\n").unwrap();
+        let rules = Rules {
+            step: 3,
+            teleport: 2,
+            ..Rules::QUEST
+        };
+        assert!(day.with_rules(rules).is_ok());
     }
 }
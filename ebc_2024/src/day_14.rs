@@ -1,45 +1,218 @@
 #[allow(unused_imports)]
 use helper::{print, println, Dijkstra, Error, HashMap, HashSet, Lines, LinesOpt, Point3D};
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
 
 #[derive(Debug)]
-enum Direction {
+pub enum Direction {
     X(isize),
     Y(isize),
     Z(isize),
 }
 
 #[derive(Debug)]
-struct Branch {
-    directions: Vec<Direction>,
-    leaf: Point3D<isize>,
+pub struct Branch {
+    pub directions: Vec<Direction>,
 }
 
 impl Branch {
-    fn grow(&mut self) -> HashSet<Point3D<isize>> {
-        let mut segments = HashSet::default();
+    // Every cell the branch grows through in order, ending at its leaf.
+    fn grow(&self) -> Vec<Point3D<isize>> {
+        let mut segments = Vec::new();
+        let mut leaf = Point3D::new(0, 0, 0);
 
         for dir in self.directions.iter() {
             macro_rules! grow {
                 ($delta:expr, $x:expr, $y:expr, $z:expr) => {
                     for _ in 0..$delta {
-                        self.leaf.x += $x;
-                        self.leaf.y += $y;
-                        self.leaf.z += $z;
-                        segments.insert(self.leaf);
+                        leaf.x += $x;
+                        leaf.y += $y;
+                        leaf.z += $z;
+                        segments.push(leaf);
                     }
                 };
             }
             match dir {
-                Direction::X(delta) => grow!(delta.unsigned_abs(), delta / delta.abs(), 0, 0),
-                Direction::Y(delta) => grow!(delta.unsigned_abs(), 0, delta / delta.abs(), 0),
-                Direction::Z(delta) => grow!(delta.unsigned_abs(), 0, 0, delta / delta.abs()),
+                Direction::X(delta) => grow!(delta.unsigned_abs(), delta.signum(), 0, 0),
+                Direction::Y(delta) => grow!(delta.unsigned_abs(), 0, delta.signum(), 0),
+                Direction::Z(delta) => grow!(delta.unsigned_abs(), 0, 0, delta.signum()),
             }
         }
         segments
     }
 }
 
+/// Something odd about how the branches grow, with the lines of the notes the branches are on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GrowthProblem {
+    /// A step that doesn't grow anything, numbered from 1 along the branch
+    ZeroStep { line: usize, step: usize },
+    /// The first cell of a branch below the ground, which is at height 0
+    BelowGround { line: usize, at: Point3D<isize> },
+    /// The first cell off the trunk a branch shares with an earlier one
+    Crossing {
+        line: usize,
+        other: usize,
+        at: Point3D<isize>,
+    },
+    /// Cells which can't be reached from the bottom of the trunk, with every branch in them
+    Disconnected {
+        lines: Vec<usize>,
+        at: Point3D<isize>,
+    },
+}
+
+impl std::fmt::Display for GrowthProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let at = |p: &Point3D<isize>| format!("({}, {}, {})", p.x, p.y, p.z);
+        match self {
+            Self::ZeroStep { line, step } => write!(f, "Line {line}: step {step} has length 0"),
+            Self::BelowGround { line, at: p } => {
+                write!(f, "Line {line}: goes below ground at {}", at(p))
+            }
+            Self::Crossing { line, other, at: p } => {
+                write!(f, "Line {line}: crosses line {other} at {}", at(p))
+            }
+            Self::Disconnected { lines, at: p } => {
+                let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+                write!(
+                    f,
+                    "Line{} {}: not connected to the trunk at {}",
+                    if lines.len() == 1 { "" } else { "s" },
+                    lines.join(", "),
+                    at(p)
+                )
+            }
+        }
+    }
+}
+
+/// What a cell of the grown tree is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    Trunk,
+    Branch,
+    Leaf,
+}
+
+impl Part {
+    fn color(&self) -> [u8; 3] {
+        match self {
+            Self::Trunk => [101, 67, 33],
+            Self::Branch => [196, 154, 108],
+            Self::Leaf => [60, 179, 60],
+        }
+    }
+}
+
+/// Every cell of the grown tree.  The trunk is the column of cells at x 0 and z 0, and a cell
+/// where a branch ends is a leaf even when another branch grows through it.
+pub struct Tree {
+    pub cells: HashMap<Point3D<isize>, Part>,
+    pub leaves: Vec<Point3D<isize>>,
+}
+
+impl Tree {
+    /// The tree as cubes of unit size with a corner at each cell's coordinates, keeping only the
+    /// faces between a cell and empty space.  Faces wind counterclockwise seen from outside.
+    pub fn mesh(&self) -> VoxelMesh {
+        // Each axis with the two after it, so that the second crossed with the third points
+        // along the first
+        const AXES: [[usize; 3]; 3] = [[0, 1, 2], [1, 2, 0], [2, 0, 1]];
+
+        let mut cells: Vec<(Point3D<isize>, Part)> =
+            self.cells.iter().map(|(p, part)| (*p, *part)).collect();
+        cells.sort_by_key(|(p, _)| (p.y, p.z, p.x));
+
+        let mut mesh = VoxelMesh::default();
+        for (p, part) in cells {
+            let at = [p.x, p.y, p.z];
+            for [axis, u, v] in AXES {
+                for outwards in [-1, 1] {
+                    let mut next = at;
+                    next[axis] += outwards;
+                    if self
+                        .cells
+                        .contains_key(&Point3D::new(next[0], next[1], next[2]))
+                    {
+                        continue;
+                    }
+                    let corners = if outwards == 1 {
+                        [(0, 0), (1, 0), (1, 1), (0, 1)]
+                    } else {
+                        [(0, 0), (0, 1), (1, 1), (1, 0)]
+                    };
+                    let first = mesh.vertices.len();
+                    for (du, dv) in corners {
+                        let mut corner = at;
+                        corner[axis] += (outwards == 1) as isize;
+                        corner[u] += du;
+                        corner[v] += dv;
+                        mesh.vertices.push((corner, part.color()));
+                    }
+                    mesh.faces.push([first, first + 1, first + 2, first + 3]);
+                }
+            }
+        }
+        mesh
+    }
+}
+
+/// Quads over integer corners, each corner with the colour of the cell it belongs to.
+#[derive(Default)]
+pub struct VoxelMesh {
+    pub vertices: Vec<([isize; 3], [u8; 3])>,
+    pub faces: Vec<[usize; 4]>,
+}
+
+impl VoxelMesh {
+    /// Wavefront OBJ, with the colours as the common `v x y z r g b` extension.
+    pub fn obj(&self) -> String {
+        let mut obj = format!(
+            "# {} vertices, {} faces\n",
+            self.vertices.len(),
+            self.faces.len()
+        );
+        for ([x, y, z], color) in self.vertices.iter() {
+            let [r, g, b] = color.map(|c| c as f64 / 255.);
+            obj.push_str(&format!("v {x} {y} {z} {r:.3} {g:.3} {b:.3}\n"));
+        }
+        for face in self.faces.iter() {
+            let [a, b, c, d] = face.map(|v| v + 1);
+            obj.push_str(&format!("f {a} {b} {c} {d}\n"));
+        }
+        obj
+    }
+
+    /// ASCII PLY with colours on the vertices.
+    pub fn ply(&self) -> String {
+        let mut ply = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\n\
+             property int x\nproperty int y\nproperty int z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face {}\nproperty list uchar int vertex_indices\nend_header\n",
+            self.vertices.len(),
+            self.faces.len()
+        );
+        for ([x, y, z], [r, g, b]) in self.vertices.iter() {
+            ply.push_str(&format!("{x} {y} {z} {r} {g} {b}\n"));
+        }
+        for [a, b, c, d] in self.faces.iter() {
+            ply.push_str(&format!("4 {a} {b} {c} {d}\n"));
+        }
+        ply
+    }
+
+    pub fn write_obj(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.obj())
+    }
+
+    pub fn write_ply(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.ply())
+    }
+}
+
 impl FromStr for Branch {
     type Err = Error;
 
@@ -48,10 +221,7 @@ impl FromStr for Branch {
         for dir in s.split(',') {
             directions.push(dir.parse()?);
         }
-        Ok(Self {
-            directions,
-            leaf: Point3D::new(0, 0, 0),
-        })
+        Ok(Self { directions })
     }
 }
 
@@ -59,8 +229,10 @@ impl FromStr for Direction {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let num = &s[1..];
-        match &s[..1] {
+        let Some((dir, num)) = s.split_at_checked(1) else {
+            return Err(Error::InvalidInput(format!("Bad step {s:?}")));
+        };
+        match dir {
             "U" => Ok(Self::Y(num.parse()?)),
             "D" => Ok(Self::Y(-num.parse()?)),
             "L" => Ok(Self::X(num.parse()?)),
@@ -82,87 +254,437 @@ impl Day14 {
         Self::default()
     }
 
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for line in lines.iter() {
+            day.branches.push(line.parse()?);
+        }
+        Ok(day)
+    }
+
+    /// Rejects notes where growing the branches runs into any `GrowthProblem`, which includes
+    /// the crossing branches in the quest's own example.
+    pub fn strict(self) -> Result<Self, Error> {
+        let problems: Vec<String> = self
+            .growth_problems()
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect();
+        if !problems.is_empty() {
+            return Err(Error::InvalidInput(problems.join("; ")));
+        }
+        Ok(self)
+    }
+
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    pub fn tree(&self) -> Tree {
+        let mut cells = HashMap::default();
+        let mut leaves = Vec::new();
+        for branch in self.branches.iter() {
+            let segments = branch.grow();
+            for p in segments.iter() {
+                let part = if p.x == 0 && p.z == 0 {
+                    Part::Trunk
+                } else {
+                    Part::Branch
+                };
+                cells.entry(*p).or_insert(part);
+            }
+            if let Some(leaf) = segments.last() {
+                cells.insert(*leaf, Part::Leaf);
+                if !leaves.contains(leaf) {
+                    leaves.push(*leaf);
+                }
+            }
+        }
+        Tree { cells, leaves }
+    }
+
+    /// Everything odd about how the branches grow, branch by branch in the order of the notes,
+    /// then any pieces of the tree not connected to the trunk.  Cells on the trunk are shared by
+    /// design, so only cells off it count as crossings.
+    pub fn growth_problems(&self) -> Vec<GrowthProblem> {
+        let on_trunk = |p: &Point3D<isize>| p.x == 0 && p.z == 0;
+        let mut problems = Vec::new();
+        // The first branch to grow through each cell off the trunk
+        let mut grown: HashMap<Point3D<isize>, usize> = HashMap::default();
+        let mut grown_by = Vec::new();
+
+        for (idx, branch) in self.branches.iter().enumerate() {
+            let line = idx + 1;
+            for (step, dir) in branch.directions.iter().enumerate() {
+                if let Direction::X(0) | Direction::Y(0) | Direction::Z(0) = dir {
+                    problems.push(GrowthProblem::ZeroStep {
+                        line,
+                        step: step + 1,
+                    });
+                }
+            }
+
+            let cells = branch.grow();
+            if let Some(p) = cells.iter().find(|p| p.y < 0) {
+                problems.push(GrowthProblem::BelowGround { line, at: *p });
+            }
+
+            let mut crossed = Vec::new();
+            for p in cells.iter().filter(|p| !on_trunk(p)) {
+                match grown.get(p) {
+                    Some(other) if *other != line && !crossed.contains(other) => {
+                        problems.push(GrowthProblem::Crossing {
+                            line,
+                            other: *other,
+                            at: *p,
+                        });
+                        crossed.push(*other);
+                    }
+                    Some(_) => (),
+                    None => {
+                        grown.insert(*p, line);
+                    }
+                }
+            }
+            grown_by.push(cells);
+        }
+
+        // Flood out from the bottom of the trunk, then again from the lowest cell left over
+        // until every cell belongs to a piece
+        let tree = self.tree();
+        let mut cells: Vec<Point3D<isize>> = tree.cells.keys().copied().collect();
+        cells.sort_by_key(|p| (!(on_trunk(p) && p.y == 1), p.y, p.z, p.x));
+        let trunk = Point3D::new(0, 1, 0);
+        let mut piece: HashMap<Point3D<isize>, usize> = HashMap::default();
+        for (id, start) in cells.iter().enumerate() {
+            if piece.contains_key(start) {
+                continue;
+            }
+            piece.insert(*start, id);
+            let mut work = vec![*start];
+            while let Some(p) = work.pop() {
+                for n in p.cardinal_neighbors() {
+                    if tree.cells.contains_key(&n) && !piece.contains_key(&n) {
+                        piece.insert(n, id);
+                        work.push(n);
+                    }
+                }
+            }
+            if piece.get(&trunk) == Some(&id) {
+                continue;
+            }
+            let lines = grown_by
+                .iter()
+                .enumerate()
+                .filter(|(_, cells)| cells.iter().any(|p| piece.get(p) == Some(&id)))
+                .map(|(idx, _)| idx + 1)
+                .collect();
+            problems.push(GrowthProblem::Disconnected { lines, at: *start });
+        }
+        problems
+    }
+
     fn find_murkiness_to_trunk(
-        tree: &HashSet<Point3D<isize>>,
+        tree: &HashMap<Point3D<isize>, Part>,
         from: Point3D<isize>,
     ) -> Vec<(isize, usize)> {
-        Dijkstra::find_all(from, |p: Point3D<isize>| {
+        let mut murkiness: Vec<(isize, usize)> = Dijkstra::find_all(from, |p: Point3D<isize>| {
             let neighbors = p.cardinal_neighbors();
 
             neighbors
                 .into_iter()
-                .filter(|p| tree.contains(p))
+                .filter(|p| tree.contains_key(p))
                 .map(|p| (1, p, p.x == 0 && p.z == 0))
         })
         .iter()
         .copied()
         .map(|(dist, p)| (p.y, dist))
-        .collect()
+        .collect();
+        // The search never reports where it started from
+        if from.x == 0 && from.z == 0 {
+            murkiness.push((from.y, 0));
+        }
+        murkiness
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        let segments = self.branches[0].grow();
-        Ok(segments
-            .iter()
-            .map(|Point3D { y, .. }| *y)
-            .max()
-            .unwrap()
-            .into())
-    }
+    /// Sum of the distances from every leaf to each trunk cell, by the trunk cell's height.
+    /// Leaves which can't reach a trunk cell don't count towards it.
+    ///
+    /// There are far fewer trunk cells than leaves, so this runs a breadth first search out from
+    /// each trunk cell over the tree flattened to indices, rather than one from each leaf.
+    /// Nothing depends on the branches not overlapping or forming loops.
+    pub fn murkiness(&self) -> Vec<(isize, usize)> {
+        const NONE: u32 = u32::MAX;
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        Ok(self
-            .branches
-            .iter_mut()
-            .fold(HashSet::default(), |mut segments, branch| {
-                branch.grow().iter().for_each(|s| {
-                    let _ = segments.insert(*s);
-                });
-                segments
+        let tree = self.tree();
+        let mut cells: Vec<Point3D<isize>> = tree.cells.keys().copied().collect();
+        cells.sort_by_key(|p| (p.y, p.z, p.x));
+        let index: HashMap<Point3D<isize>, u32> = cells
+            .iter()
+            .enumerate()
+            .map(|(idx, p)| (*p, idx as u32))
+            .collect();
+        let neighbors: Vec<[u32; 6]> = cells
+            .iter()
+            .map(|p| {
+                p.cardinal_neighbors()
+                    .map(|p| index.get(&p).copied().unwrap_or(NONE))
             })
-            .len()
-            .into())
-    }
+            .collect();
+        let leaves: Vec<u32> = tree.leaves.iter().map(|leaf| index[leaf]).collect();
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        let mut leaves = HashSet::default();
-        let tree = self
-            .branches
-            .iter_mut()
-            .fold(HashSet::default(), |mut segments, branch| {
-                branch.grow().iter().for_each(|s| {
-                    let _ = segments.insert(*s);
-                });
-                leaves.insert(branch.leaf);
-                segments
-            });
+        let mut murkiness = Vec::new();
+        let mut dist = vec![NONE; cells.len()];
+        let mut work = Vec::with_capacity(cells.len());
+        for (trunk, p) in cells.iter().enumerate() {
+            if p.x != 0 || p.z != 0 {
+                continue;
+            }
+            dist.fill(NONE);
+            dist[trunk] = 0;
+            work.clear();
+            work.push(trunk as u32);
+            let mut next = 0;
+            while let Some(at) = work.get(next).copied() {
+                next += 1;
+                for n in neighbors[at as usize] {
+                    if n != NONE && dist[n as usize] == NONE {
+                        dist[n as usize] = dist[at as usize] + 1;
+                        work.push(n);
+                    }
+                }
+            }
 
+            let reached: Vec<usize> = leaves
+                .iter()
+                .map(|leaf| dist[*leaf as usize])
+                .filter(|dist| *dist != NONE)
+                .map(|dist| dist as usize)
+                .collect();
+            if !reached.is_empty() {
+                murkiness.push((p.y, reached.iter().sum()));
+            }
+        }
+        murkiness
+    }
+
+    // Reference implementation for stress testing: the murkiness from a search out of every leaf.
+    fn murkiness_from_leaves(&self) -> Vec<(isize, usize)> {
+        let tree = self.tree();
         let mut murkiness: HashMap<isize, usize> = HashMap::default();
-        leaves.iter().for_each(|l| {
-            Self::find_murkiness_to_trunk(&tree, *l)
+        tree.leaves.iter().for_each(|l| {
+            Self::find_murkiness_to_trunk(&tree.cells, *l)
                 .iter()
                 .for_each(|(y, dist)| *murkiness.entry(*y).or_default() += dist)
         });
+        let mut murkiness: Vec<(isize, usize)> = murkiness.into_iter().collect();
+        murkiness.sort();
+        murkiness
+    }
 
-        Ok(murkiness.values().min().copied().unwrap().into())
+    pub fn murkiness_csv(&self) -> String {
+        let mut csv = String::from("height,murkiness\n");
+        for (y, murkiness) in self.murkiness() {
+            csv.push_str(&format!("{y},{murkiness}\n"));
+        }
+        csv
+    }
+
+    pub fn part1(&mut self) -> Result<isize, Error> {
+        let branch = self
+            .branches
+            .first()
+            .ok_or_else(|| Error::InvalidInput("No branches".into()))?;
+        branch
+            .grow()
+            .iter()
+            .map(|Point3D { y, .. }| *y)
+            .max()
+            .ok_or_else(|| Error::InvalidInput("First branch doesn't grow".into()))
+    }
+
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        Ok(self.tree().cells.len())
+    }
+
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        self.murkiness()
+            .iter()
+            .map(|(_, murkiness)| *murkiness)
+            .min()
+            .ok_or(Error::Unsolved)
+    }
+
+    pub(crate) fn part3_leaves(&mut self) -> Result<usize, Error> {
+        self.murkiness_from_leaves()
+            .iter()
+            .map(|(_, murkiness)| *murkiness)
+            .min()
+            .ok_or(Error::Unsolved)
     }
 }
 
 impl helper::Runner for Day14 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for line in lines.iter() {
-            self.branches.push(line.parse()?);
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{generator, Rng};
+
+    const EXAMPLE: &str = "U5,R3,D2,L5,U4,R5,D2\nU6,L1,D2,R3,U2,L1\n";
+    const EXAMPLE_3: &str = "U20,L1,B1,L2,B1,R2,L1,F1,U1\nU10,F1,B1,R1,L1,B1,L1,F1,R2,U1\n\
+                             U30,L2,F1,R1,B1,R1,F2,U1,F1\nU25,R1,L2,B1,U1,R2,F1,L2\n\
+                             U16,L1,B1,L1,B3,L1,B1,F1\n";
+
+    fn day(notes: &str) -> Day14 {
+        Day14::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(day("U5,R3,D2,L5,U4,R5,D2\n").part1().unwrap(), 7);
+        assert_eq!(day(EXAMPLE).part2().unwrap(), 32);
+        assert_eq!(day(EXAMPLE).part3().unwrap(), 5);
+        assert_eq!(day(EXAMPLE_3).part3().unwrap(), 46);
+    }
+
+    #[test]
+    fn murkiness_matches_searching_from_every_leaf() {
+        let mut notes = vec![
+            EXAMPLE.to_string(),
+            EXAMPLE_3.to_string(),
+            // A branch looping back on itself, and one running along the trunk
+            "U3,R1,D1,L1,U2,R1\nU1,R2,U3,L2\n".to_string(),
+        ];
+        let generate = generator(2024, 14).unwrap();
+        let mut rng = Rng::new(14);
+        notes.extend((0..50).map(|_| generate(&mut rng, 4)));
+        for notes in notes {
+            let day = day(&notes);
+            assert_eq!(day.murkiness(), day.murkiness_from_leaves(), "{notes}");
+        }
+    }
+
+    #[test]
+    fn growth_problems() {
+        // The quest's own examples only cross each other, which strict parsing still rejects
+        assert_eq!(
+            day(EXAMPLE).growth_problems(),
+            [GrowthProblem::Crossing {
+                line: 2,
+                other: 1,
+                at: Point3D::new(-2, 5, 0)
+            }]
+        );
+        assert!(matches!(
+            day(EXAMPLE).strict(),
+            Err(Error::InvalidInput(e)) if e == "Line 2: crosses line 1 at (-2, 5, 0)"
+        ));
+        assert!(day("U2,R2\nU1,R1,U2\n").strict().is_err());
+        assert!(day(EXAMPLE_3).growth_problems().is_empty());
+        assert!(day(EXAMPLE_3).strict().is_ok());
+
+        // Growing along the ground is fine, going under it isn't
+        assert!(day("U1,D1,R2\n").growth_problems().is_empty());
+        assert_eq!(
+            day("U1,D2\n").growth_problems(),
+            [GrowthProblem::BelowGround {
+                line: 1,
+                at: Point3D::new(0, -1, 0)
+            }]
+        );
+        assert_eq!(
+            day("U2\nU1,R0\n").growth_problems(),
+            [GrowthProblem::ZeroStep { line: 2, step: 2 }]
+        );
+        assert!(day("U2\nU1,R0\n").strict().is_err());
+    }
+
+    #[test]
+    fn empty_or_malformed_notes() {
+        for notes in ["", "U2,,R1\n", "X3\n", "U\n"] {
+            let result = Day14::from_notes(notes.as_bytes()).and_then(|mut day| day.part1());
+            assert!(result.is_err(), "{notes:?}");
+        }
+    }
+
+    #[test]
+    fn mesh_keeps_only_outside_faces() {
+        // A lone cube, then two stacked ones hiding the faces between them
+        let mesh = day("U1\n").tree().mesh();
+        assert_eq!((mesh.vertices.len(), mesh.faces.len()), (24, 6));
+        assert_eq!(mesh.faces[0], [0, 1, 2, 3]);
+        let mesh = day("U2\n").tree().mesh();
+        assert_eq!((mesh.vertices.len(), mesh.faces.len()), (40, 10));
+
+        // Every face of a cell with no cell beside it, and four corners to each
+        let tree = day(EXAMPLE).tree();
+        let open: usize = tree
+            .cells
+            .keys()
+            .map(|p| {
+                p.cardinal_neighbors()
+                    .iter()
+                    .filter(|n| !tree.cells.contains_key(n))
+                    .count()
+            })
+            .sum();
+        let mesh = tree.mesh();
+        assert_eq!(mesh.faces.len(), open);
+        assert_eq!(mesh.vertices.len(), open * 4);
+    }
+
+    #[test]
+    fn exports() {
+        let lone = day("U1\n");
+        let mesh = lone.tree().mesh();
+
+        let obj = mesh.obj();
+        let lines: Vec<&str> = obj.lines().collect();
+        assert_eq!(lines.len(), 1 + 24 + 6);
+        assert_eq!(lines[0], "# 24 vertices, 6 faces");
+        assert_eq!(lines[1], "v 0 1 0 0.235 0.702 0.235");
+        assert_eq!(lines[25], "f 1 2 3 4");
+
+        let ply = mesh.ply();
+        let lines: Vec<&str> = ply.lines().collect();
+        assert_eq!(
+            lines[..11],
+            [
+                "ply",
+                "format ascii 1.0",
+                "element vertex 24",
+                "property int x",
+                "property int y",
+                "property int z",
+                "property uchar red",
+                "property uchar green",
+                "property uchar blue",
+                "element face 6",
+                "property list uchar int vertex_indices",
+            ]
+        );
+        assert_eq!(lines[11], "end_header");
+        assert_eq!(lines.len(), 12 + 24 + 6);
+        assert_eq!(lines[12], "0 1 0 60 179 60");
+        assert_eq!(lines[36], "4 0 1 2 3");
+
+        assert_eq!(lone.murkiness_csv(), "height,murkiness\n1,0\n");
+        assert_eq!(day("U2\n").murkiness_csv(), "height,murkiness\n1,1\n2,0\n");
+    }
+}
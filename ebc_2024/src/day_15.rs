@@ -1,189 +1,491 @@
 #[allow(unused_imports)]
-use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt, Point2D};
-use std::{
-    collections::{BTreeMap, VecDeque},
-    ops::RangeInclusive,
-};
-
-#[derive(Copy, Clone)]
-struct Path {
-    to: Point2D<u8>,
-    to_picked_up: u32,
-    to_last_pickup: u32,
-    to_dist: usize,
+use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt};
+use std::collections::VecDeque;
+
+use crate::grid::GridGraph;
+
+/// A walk around the garden, in `(x, y)` map positions.
+pub struct Route {
+    pub length: usize,
+    /// Every cell stepped on in order, starting and ending at the entrance
+    pub cells: Vec<(usize, usize)>,
+    /// Herbs in the order they were picked, with the index in `cells` where each was picked
+    pub pickups: Vec<(char, usize)>,
 }
 
-#[derive(Default)]
-pub struct Day15 {
-    map: Vec<Vec<char>>,
-    start: Point2D<u8>,
-    herbs: HashMap<char, Vec<Point2D<u8>>>,
-    paths: HashMap<Point2D<u8>, Vec<Path>>,
+// A walk over cell indices, with the cells where herbs are picked up.
+struct Walk {
+    cells: Vec<usize>,
+    pickups: Vec<usize>,
 }
 
-impl Day15 {
-    pub fn new() -> Self {
-        Self::default()
+// The map flattened to cell indices, with the herb kind of every open cell as a bit.
+struct Garden {
+    graph: GridGraph,
+    open: Vec<bool>,
+    herbs: Vec<u64>,
+}
+
+impl Garden {
+    fn new(map: &[Vec<char>]) -> Self {
+        let is_open = |c: char| c == '.' || c.is_ascii_uppercase();
+        let graph = GridGraph::new(map, |from, to| (is_open(from) && is_open(to)).then_some(1));
+        let open = (0..graph.len())
+            .map(|cell| is_open(graph.cell(cell)))
+            .collect();
+        let herbs = (0..graph.len())
+            .map(|cell| match graph.cell(cell) {
+                c @ 'A'..='Z' => 1 << (c as u8 - b'A'),
+                _ => 0,
+            })
+            .collect();
+        Self { graph, open, herbs }
     }
 
-    fn paths_from(&self, from: Point2D<u8>) -> Vec<Path> {
-        let start = self.map[from.y as usize][from.x as usize];
-        let mut work = VecDeque::new();
-        work.push_front((0, from, 0));
-        let mut seen = HashSet::default();
-        seen.insert(from);
+    // Cells on a shortest path from `from` to `to` within `region`, not including `from`.
+    fn path(&self, from: usize, to: usize, region: &[bool]) -> Vec<usize> {
+        let mut path = self.graph.paths_within(&[from], region).path(to).unwrap();
+        path.remove(0);
+        path
+    }
 
-        let mut paths = Vec::new();
-        while let Some((dist, at, picked_up)) = work.pop_front() {
-            for next in [
-                Point2D::new(at.x - 1, at.y),
-                Point2D::new(at.x + 1, at.y),
-                Point2D::new(at.x, at.y - 1),
-                Point2D::new(at.x, at.y + 1),
-            ] {
-                if !seen.insert(next) {
-                    continue;
+    /// Shortest walk from `start` through `region` collecting every herb kind found there and
+    /// coming back.
+    ///
+    /// A piece of the region hanging off a single cell whose herb kinds grow nowhere else has to
+    /// be walked in to and out of through that cell, so it is solved as a tour of its own from
+    /// there.  What remains only has to pass through the cell, which is marked with a waypoint
+    /// kind of its own.  Pieces without herbs are never worth entering and are dropped.
+    fn tour(&self, start: usize, region: &[bool]) -> Result<Walk, Error> {
+        const NONE: usize = usize::MAX;
+
+        // Iterative Tarjan: depth first order, parent, low link and subtree size of every cell
+        // reachable from `start`
+        let mut order = Vec::new();
+        let mut disc = vec![NONE; self.graph.len()];
+        let mut low = vec![NONE; self.graph.len()];
+        let mut parent = vec![NONE; self.graph.len()];
+        let mut size = vec![0; self.graph.len()];
+        disc[start] = 0;
+        low[start] = 0;
+        order.push(start);
+        let mut stack = vec![(start, 0)];
+        while let Some((cell, dir)) = stack.last_mut() {
+            let cell = *cell;
+            if *dir == 4 {
+                stack.pop();
+                size[cell] = order.len() - disc[cell];
+                if parent[cell] != NONE {
+                    low[parent[cell]] = low[parent[cell]].min(low[cell]);
                 }
-                match self.map[next.y as usize][next.x as usize] {
-                    '.' => work.push_back((dist + 1, next, picked_up)),
-                    c if c.is_ascii_alphabetic() && c != start => {
-                        let last_pickup = 1u32 << (c as u8 - b'A');
-                        if picked_up & last_pickup == 0 {
-                            let picked_up = picked_up | last_pickup;
-                            paths.push(Path {
-                                to: next,
-                                to_picked_up: picked_up,
-                                to_last_pickup: last_pickup,
-                                to_dist: dist + 1,
-                            });
-                        }
-                        work.push_back((dist + 1, next, picked_up));
-                    }
-                    _ => {}
+                continue;
+            }
+            let next = self
+                .graph
+                .step(cell, *dir)
+                .filter(|(next, _)| region[*next]);
+            *dir += 1;
+            let Some((next, _)) = next else {
+                continue;
+            };
+            if disc[next] == NONE {
+                disc[next] = order.len();
+                low[next] = disc[next];
+                parent[next] = cell;
+                order.push(next);
+                stack.push((next, 0));
+            } else if next != parent[cell] {
+                low[cell] = low[cell].min(disc[next]);
+            }
+        }
+
+        let n = order.len();
+        let wanted = (0..self.graph.len())
+            .filter(|cell| region[*cell])
+            .fold(0, |kinds, cell| kinds | self.herbs[cell]);
+        let mut subtree: Vec<u64> = order.iter().map(|cell| self.herbs[*cell]).collect();
+        for idx in (1..n).rev() {
+            subtree[disc[parent[order[idx]]]] |= subtree[idx];
+        }
+        let mut before = vec![0u64; n + 1];
+        let mut after = vec![0u64; n + 1];
+        for idx in 0..n {
+            before[idx + 1] = before[idx] | self.herbs[order[idx]];
+            after[n - idx - 1] = after[n - idx] | self.herbs[order[n - idx - 1]];
+        }
+        if before[n] != wanted {
+            return Err(Error::Unsolved);
+        }
+
+        let mut remaining = vec![false; self.graph.len()];
+        order.iter().for_each(|cell| remaining[*cell] = true);
+        let mut pieces: HashMap<usize, Vec<Walk>> = HashMap::default();
+        let mut idx = 1;
+        while idx < n {
+            let cell = order[idx];
+            let through = parent[cell];
+            let end = idx + size[cell];
+            let separate = low[cell] >= disc[through]
+                && !(through == start && size[cell] == n - 1)
+                && subtree[idx] & (before[idx] | after[end]) == 0;
+            if !separate {
+                idx += 1;
+                continue;
+            }
+
+            order[idx..end]
+                .iter()
+                .for_each(|cell| remaining[*cell] = false);
+            if subtree[idx] != 0 {
+                let mut piece = vec![false; self.graph.len()];
+                order[idx..end].iter().for_each(|cell| piece[*cell] = true);
+                piece[through] = true;
+                let walk = self.tour(through, &piece)?;
+                pieces.entry(through).or_default().push(walk);
+            }
+            idx = end;
+        }
+
+        let waypoints: Vec<usize> = pieces.keys().copied().collect();
+        let first_waypoint = 64 - wanted.leading_zeros() as usize;
+        if first_waypoint + waypoints.len() > 64 {
+            return Err(Error::InvalidInput(format!(
+                "Too many herb kinds and waypoints to collect from cell {start}"
+            )));
+        }
+        let mut kinds: HashMap<usize, u64> = HashMap::default();
+        for cell in order.iter().copied().filter(|cell| remaining[*cell]) {
+            if self.herbs[cell] != 0 {
+                kinds.insert(cell, self.herbs[cell]);
+            }
+        }
+        for (bit, cell) in waypoints.iter().enumerate() {
+            *kinds.entry(*cell).or_default() |= 1 << (first_waypoint + bit);
+        }
+
+        // Walk between the stops, taking each side trip on reaching the cell it hangs off
+        let stops = self.collect(start, &remaining, &kinds)?;
+        let mut walk = Walk {
+            cells: vec![start],
+            pickups: Vec::new(),
+        };
+        let mut picked_up = self.herbs[start];
+        if picked_up != 0 {
+            walk.pickups.push(0);
+        }
+        for (idx, stop) in stops.iter().enumerate() {
+            if idx != 0 {
+                walk.cells
+                    .extend(self.path(stops[idx - 1], *stop, &remaining));
+                if self.herbs[*stop] & !picked_up != 0 {
+                    picked_up |= self.herbs[*stop];
+                    walk.pickups.push(walk.cells.len() - 1);
                 }
             }
+            for piece in pieces.remove(stop).unwrap_or_default() {
+                let offset = walk.cells.len() - 1;
+                walk.pickups.extend(
+                    piece
+                        .pickups
+                        .iter()
+                        .filter(|p| **p != 0)
+                        .map(|p| p + offset),
+                );
+                walk.cells.extend(piece.cells.iter().skip(1));
+            }
         }
-        paths
+        Ok(walk)
     }
 
-    fn find_paths(&mut self, start: Option<Point2D<u8>>, x_range: Option<RangeInclusive<usize>>) {
-        if let Some(start) = start {
-            self.start = start;
-        } else {
-            self.start = Point2D::new(self.map[0].iter().position(|c| *c == '.').unwrap() as u8, 1);
-            self.map[0][self.start.x as usize] = '#';
-            self.map[1][self.start.x as usize] = 'Z';
-        }
+    // Shortest walk from `start` picking up every kind in `kinds` and coming back.  Held-Karp over
+    // (kinds collected, last place) with the places being the start and every cell with a kind,
+    // the kinds packed in to dense bits and the table flat.  Returns the cells stopped at, starting
+    // and ending with `start`.
+    fn collect(
+        &self,
+        start: usize,
+        region: &[bool],
+        kinds: &HashMap<usize, u64>,
+    ) -> Result<Vec<usize>, Error> {
+        const MAX_STATES: usize = 1 << 26;
+        const NONE: u32 = u32::MAX;
 
-        let x_range = if let Some(x_range) = x_range {
-            x_range
-        } else {
-            0..=self.map[0].len()
+        let mut places = vec![start];
+        places.extend(kinds.keys().copied().filter(|cell| *cell != start));
+        places[1..].sort();
+        let bits: Vec<u64> = {
+            let all = places
+                .iter()
+                .fold(0, |all, cell| all | kinds.get(cell).copied().unwrap_or(0));
+            (0..64)
+                .map(|bit| 1 << bit)
+                .filter(|bit| all & bit != 0)
+                .collect()
         };
+        let place_kinds: Vec<usize> = places
+            .iter()
+            .map(|cell| {
+                let sparse = kinds.get(cell).copied().unwrap_or(0);
+                bits.iter()
+                    .enumerate()
+                    .filter(|(_, bit)| sparse & **bit != 0)
+                    .fold(0, |dense, (idx, _)| dense | 1 << idx)
+            })
+            .collect();
+        let init = place_kinds[0];
+        let all = (1usize << bits.len()) - 1;
+        if init == all {
+            return Ok(vec![start]);
+        }
+
+        let n = places.len();
+        if (all + 1).saturating_mul(n) > MAX_STATES {
+            return Err(Error::InvalidInput(format!(
+                "{} kinds over {n} places is too many states to search",
+                bits.len()
+            )));
+        }
+        let mut dist = vec![NONE; n * n];
+        for (from, cell) in places.iter().enumerate() {
+            let paths = self.graph.paths_within(&[*cell], region);
+            for (to, cell) in places.iter().enumerate() {
+                if let Some(cost) = paths.cost(*cell) {
+                    dist[from * n + to] = cost as u32;
+                }
+            }
+        }
 
-        self.herbs.clear();
-        self.paths.clear();
-        for (y, row) in self.map.iter().enumerate() {
-            for (x, c) in row.iter().enumerate() {
-                if !x_range.contains(&x) {
+        // best[kinds * n + place]: shortest walk from the start collecting `kinds` and ending at
+        // `place`, which only ever grows the kinds collected so masks can be filled in order
+        let mut best = vec![NONE; (all + 1) * n];
+        best[init * n] = 0;
+        for picked_up in init..=all {
+            for at in 0..n {
+                let at_dist = best[picked_up * n + at];
+                if at_dist == NONE {
                     continue;
                 }
-                if c.is_ascii_alphabetic() {
-                    self.herbs
-                        .entry(*c)
-                        .or_default()
-                        .push(Point2D::new(x as u8, y as u8));
+                for to in 1..n {
+                    let step = dist[at * n + to];
+                    if place_kinds[to] & !picked_up == 0 || step == NONE {
+                        continue;
+                    }
+                    let next = (picked_up | place_kinds[to]) * n + to;
+                    best[next] = best[next].min(at_dist + step);
                 }
             }
         }
 
-        let herbs: Vec<char> = self.herbs.keys().copied().collect();
-        for from_c in herbs.iter() {
-            for from in self.herbs.get(from_c).unwrap() {
-                let paths = self.paths_from(*from);
-                self.paths.insert(*from, paths);
+        let Some((_, last)) = (1..n)
+            .filter(|at| best[all * n + at] != NONE && dist[at * n] != NONE)
+            .map(|at| (best[all * n + at] + dist[at * n], at))
+            .min()
+        else {
+            return Err(Error::Unsolved);
+        };
+
+        // Walk back through the table for a previous stop which leads here at the same distance
+        let mut stops = vec![start, places[last]];
+        let (mut picked_up, mut at) = (all, last);
+        while at != 0 {
+            let gained = place_kinds[at];
+            let mut prev = None;
+            let mut kept = gained;
+            loop {
+                kept = kept.wrapping_sub(1) & gained;
+                if kept == gained {
+                    break;
+                }
+                let before = (picked_up & !gained) | kept;
+                prev = (0..n).find(|from| {
+                    let (from_dist, step) = (best[before * n + from], dist[from * n + at]);
+                    from_dist != NONE
+                        && step != NONE
+                        && from_dist + step == best[picked_up * n + at]
+                });
+                if let Some(from) = prev {
+                    (picked_up, at) = (before, from);
+                    break;
+                }
             }
+            if prev.is_none() {
+                return Err(Error::Unsolved);
+            }
+            stops.push(places[at]);
         }
+        stops.reverse();
+        Ok(stops)
     }
+}
 
-    fn find_full_path(&mut self) -> Result<usize, Error> {
-        let mut work: BTreeMap<usize, HashSet<(Point2D<u8>, u32)>> = BTreeMap::default();
-        work.entry(2)
-            .or_default()
-            .insert((self.start, 1u32 << (b'Z' - b'A')));
+#[derive(Default)]
+pub struct Day15 {
+    map: Vec<Vec<char>>,
+}
 
-        let mut seen: [HashMap<u32, usize>; 255 * 255] =
-            std::array::from_fn(|_| Default::default());
+impl Day15 {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        while let Some((dist, cur_work)) = work.pop_first() {
-            for (at, picked_up) in cur_work {
-                if picked_up.count_ones() as usize == self.herbs.len() {
-                    if at == self.start {
-                        return Ok(dist);
-                    }
-                    // return home
-                    for Path { to, to_dist, .. } in self.paths.get(&at).unwrap().iter().copied() {
-                        if to != self.start {
-                            continue;
-                        }
-                        work.entry(dist + to_dist)
-                            .or_default()
-                            .insert((to, picked_up));
-                    }
-                    continue;
-                }
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        for line in lines.iter() {
+            day.map.push(line.chars().collect());
+        }
+        Ok(day)
+    }
 
-                for Path {
-                    to,
-                    to_picked_up,
-                    to_last_pickup,
-                    to_dist,
-                } in self.paths.get(&at).unwrap().iter().copied()
-                {
-                    if picked_up & to_last_pickup != 0 {
-                        continue;
-                    }
-                    if picked_up & to_picked_up == to_picked_up {
-                        continue;
-                    }
-                    let dist = dist + to_dist;
-                    let at = to;
-                    let picked_up = picked_up | to_picked_up;
-
-                    let seen_idx = (at.y as usize * 255) + at.x as usize;
-                    let seen = seen[seen_idx].entry(picked_up).or_insert(usize::MAX);
-                    if *seen > dist {
-                        *seen = dist;
-                        work.entry(dist).or_default().insert((at, picked_up));
+    pub fn map(&self) -> &[Vec<char>] {
+        &self.map
+    }
+
+    // Column of the gap in the top row of the wall, which has wall on either side of it.
+    fn entrance(&self) -> Result<usize, Error> {
+        let row = self.map.first().map_or(&[][..], |row| &row[..]);
+        let x = row
+            .iter()
+            .position(|c| *c == '.')
+            .ok_or_else(|| Error::InvalidInput("No entrance in the top row".into()))?;
+        if x == 0 || x + 1 == row.len() {
+            return Err(Error::InvalidInput(format!(
+                "Entrance at column {x} is on the edge of the garden"
+            )));
+        }
+        Ok(x)
+    }
+
+    /// Shortest walk from the entrance collecting at least one herb of every kind and back.
+    pub fn shortest_route(&self) -> Result<Route, Error> {
+        let entrance = self.entrance()?;
+        let garden = Garden::new(&self.map);
+        let walk = garden.tour(entrance, &garden.open)?;
+
+        let xy = |cell: usize| garden.graph.xy(cell);
+        Ok(Route {
+            length: walk.cells.len() - 1,
+            pickups: walk
+                .pickups
+                .iter()
+                .map(|idx| {
+                    let (x, y) = xy(walk.cells[*idx]);
+                    (self.map[y][x], *idx)
+                })
+                .collect(),
+            cells: walk.cells.into_iter().map(xy).collect(),
+        })
+    }
+
+    pub fn shortest_tour(&self) -> Result<usize, Error> {
+        Ok(self.shortest_route()?.length)
+    }
+
+    /// Draws the route over the map with an arrow on each cell for the way it was last left, and
+    /// numbers the herbs picked in order (1-9, then a-z), followed by a list of the pickups.
+    pub fn render_route(&self, route: &Route) -> String {
+        let mut map = self.map.clone();
+        for step in route.cells.windows(2) {
+            let ((x, y), (nx, ny)) = (step[0], step[1]);
+            map[y][x] = match (nx as isize - x as isize, ny as isize - y as isize) {
+                (1, _) => '>',
+                (-1, _) => '<',
+                (_, 1) => 'v',
+                _ => '^',
+            };
+        }
+        for (number, (_, idx)) in route.pickups.iter().enumerate() {
+            let (x, y) = route.cells[*idx];
+            map[y][x] = match number + 1 {
+                n @ 1..=9 => (b'0' + n as u8) as char,
+                n @ 10..=35 => (b'a' + (n - 10) as u8) as char,
+                _ => '*',
+            };
+        }
+
+        let mut rendered = String::new();
+        for row in map.iter() {
+            rendered.extend(row.iter());
+            rendered.push('\n');
+        }
+        rendered.push_str(&format!("Length {}\n", route.length));
+        for (number, (herb, idx)) in route.pickups.iter().enumerate() {
+            let (x, y) = route.cells[*idx];
+            rendered.push_str(&format!(
+                "{:>3}: {herb} at ({x}, {y}) after {idx} steps\n",
+                number + 1
+            ));
+        }
+        rendered
+    }
+
+    // Reference implementation for stress testing: breadth first search over (cell, herbs
+    // collected) directly on the map, starting and ending at the entrance.
+    fn find_full_path_states(&self) -> Result<usize, Error> {
+        let entrance = (self.entrance()?, 0usize);
+        let mut kinds: Vec<char> = self
+            .map
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|c| c.is_ascii_alphabetic())
+            .collect();
+        kinds.sort();
+        kinds.dedup();
+        let all = (1u32 << kinds.len()) - 1;
+
+        let mut work = VecDeque::new();
+        work.push_back((0, entrance, 0u32));
+        let mut seen = HashSet::default();
+        seen.insert((entrance, 0u32));
+        while let Some((dist, at, picked_up)) = work.pop_front() {
+            if at == entrance && picked_up == all {
+                return Ok(dist);
+            }
+            for next in [
+                (at.0.wrapping_sub(1), at.1),
+                (at.0 + 1, at.1),
+                (at.0, at.1.wrapping_sub(1)),
+                (at.0, at.1 + 1),
+            ] {
+                let Some(c) = self.map.get(next.1).and_then(|row| row.get(next.0)) else {
+                    continue;
+                };
+                let picked_up = match c {
+                    '.' => picked_up,
+                    c if c.is_ascii_alphabetic() => {
+                        picked_up | 1 << kinds.iter().position(|k| k == c).unwrap()
                     }
+                    _ => continue,
+                };
+                if seen.insert((next, picked_up)) {
+                    work.push_back((dist + 1, next, picked_up));
                 }
             }
         }
-
-        unreachable!();
+        Err(Error::Unsolved)
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        let start = (self.map[0].iter().position(|c| *c == '.').unwrap(), 1);
+    pub fn part1(&mut self) -> Result<usize, Error> {
+        let start = (self.entrance()?, 0usize);
         let mut work = VecDeque::new();
         work.push_front((0, start));
         let mut seen = HashSet::default();
-        seen.insert((start.0, 0));
         seen.insert(start);
 
         while let Some((dist, at)) = work.pop_front() {
             for next in [
-                (at.0 - 1, at.1),
+                (at.0.wrapping_sub(1), at.1),
                 (at.0 + 1, at.1),
-                (at.0, at.1 - 1),
+                (at.0, at.1.wrapping_sub(1)),
                 (at.0, at.1 + 1),
             ] {
                 if !seen.insert(next) {
                     continue;
                 }
-                match self.map[next.1][next.0] {
-                    '.' => work.push_back((dist + 1, next)),
-                    'H' => return Ok(((dist + 2) * 2).into()),
+                match self.map.get(next.1).and_then(|row| row.get(next.0)) {
+                    Some('.') => work.push_back((dist + 1, next)),
+                    Some('H') => return Ok((dist + 1) * 2),
                     _ => {}
                 }
             }
@@ -191,59 +493,131 @@ impl Day15 {
         Err(Error::Unsolved)
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        self.find_paths(None, None);
-        Ok(self.find_full_path()?.into())
+    pub fn part2(&mut self) -> Result<usize, Error> {
+        self.shortest_tour()
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
-        if self.map[0].len() != 255 {
-            self.find_paths(None, None);
-            Ok(self.find_full_path()?.into())
-        } else {
-            // split in to three columns
-            self.map[75][84] = 'Z';
-            self.map[75][85] = '#';
-            self.map[75][86] = 'Y';
-            self.map[75][168] = 'X';
-            self.map[75][169] = '#';
-            self.map[75][170] = 'Z';
-
-            let start = Point2D::new(self.map[0].iter().position(|c| *c == '.').unwrap() as u8, 1);
-            self.map[0][start.x as usize] = '#';
-            self.map[1][start.x as usize] = 'Z';
-
-            let mut min_dist = 4;
+    pub fn part3(&mut self) -> Result<usize, Error> {
+        self.shortest_tour()
+    }
 
-            for (start, x_range) in [
-                (Point2D::new(84, 75), 0..=84),
-                (Point2D::new(170, 75), 170..=255),
-                (start, 86..=168),
-            ] {
-                self.find_paths(Some(start), Some(x_range));
-                min_dist += self.find_full_path()?;
-            }
+    pub(crate) fn part2_states(&mut self) -> Result<usize, Error> {
+        self.find_full_path_states()
+    }
 
-            Ok(min_dist.into())
-        }
+    pub(crate) fn part3_states(&mut self) -> Result<usize, Error> {
+        self.find_full_path_states()
     }
 }
 
 impl helper::Runner for Day15 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for line in lines.iter() {
-            self.map.push(line.chars().collect());
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => Ok(self.part3()?.into()),
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{generator, Rng};
+
+    const EXAMPLE: &str =
+        "#####.#####\n#.........#\n#.######.##\n#.........#\n###.#.#####\n#H.......H#\n###########\n";
+    // Every herb is in a room hanging off a single doorway
+    const POCKETS: &str = "#####.#####\n#...#.#...#\n#.A.#.#.B.#\n#...#.#...#\n##.##.##.##\n\
+                           #.........#\n####.#.####\n#C...#...D#\n###########\n";
+
+    fn day(notes: &str) -> Day15 {
+        Day15::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    // The route steps between open neighbours from the entrance and back, picking every herb
+    // kind on the map where it stands, and is as short as searching the whole map finds
+    fn check_route(day: &Day15) {
+        let route = day.shortest_route().unwrap();
+        assert_eq!(route.length, route.cells.len() - 1);
+        assert_eq!(route.cells.first(), route.cells.last());
+        assert_eq!(route.cells[0].1, 0);
+        for pair in route.cells.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            assert_eq!(x0.abs_diff(x1) + y0.abs_diff(y1), 1);
+            assert!(!matches!(day.map[y1][x1], '#' | '~'));
+        }
+        let mut kinds: Vec<char> = day
+            .map
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|c| c.is_ascii_uppercase())
+            .collect();
+        kinds.sort();
+        kinds.dedup();
+        let mut picked: Vec<char> = route.pickups.iter().map(|(kind, _)| *kind).collect();
+        picked.sort();
+        assert_eq!(picked, kinds);
+        for (kind, idx) in route.pickups.iter() {
+            let (x, y) = route.cells[*idx];
+            assert_eq!(day.map[y][x], *kind);
+        }
+        assert_eq!(route.length, day.find_full_path_states().unwrap());
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(day(EXAMPLE).part1().unwrap(), 26);
+        assert_eq!(day(EXAMPLE).shortest_tour().unwrap(), 26);
+    }
+
+    #[test]
+    fn pockets_are_toured_on_their_own() {
+        let day = day(POCKETS);
+        assert_eq!(day.shortest_tour().unwrap(), 54);
+        check_route(&day);
+    }
+
+    #[test]
+    fn generated_routes() {
+        let generate = generator(2024, 15).unwrap();
+        let mut rng = Rng::new(15);
+        for _ in 0..50 {
+            check_route(&day(&generate(&mut rng, 4)));
+        }
+    }
+
+    #[test]
+    fn entrance_must_be_a_gap_in_the_wall() {
+        for notes in [
+            "",
+            "\n",
+            "###\n#H#\n###\n",
+            ".##\n.H#\n###\n",
+            "##.\n#H.\n###\n",
+        ] {
+            let mut day = day(notes);
+            assert!(
+                matches!(day.part1(), Err(Error::InvalidInput(_))),
+                "{notes:?}"
+            );
+            assert!(
+                matches!(day.part2(), Err(Error::InvalidInput(_))),
+                "{notes:?}"
+            );
+            assert!(
+                matches!(day.part2_states(), Err(Error::InvalidInput(_))),
+                "{notes:?}"
+            );
+        }
+        // Walking off the map is just not a way to go
+        assert_eq!(day("#.#\n#.H\n").part1().unwrap(), 4);
+    }
+}
@@ -1,58 +1,93 @@
 #[allow(unused_imports)]
 use helper::{print, println, Error, HashMap, HashSet, Lines, LinesOpt};
 
+use crate::math::gcd;
+
+/// Which characters of a slot are its symbols, and how many coins matching symbols on a line pay.
+#[derive(Copy, Clone, Debug)]
+pub struct Scoring {
+    /// Character positions in a slot, counted from the end when negative
+    pub positions: &'static [isize],
+    /// Matching symbols needed for a coin, each further match paying one more
+    pub min_match: usize,
+}
+
+impl Scoring {
+    /// The eyes of a cat face, paying from three of a kind.
+    pub const EYES: Self = Self {
+        positions: &[0, -1],
+        min_match: 3,
+    };
+
+    fn coins(&self, matching: usize) -> usize {
+        (matching + 1).saturating_sub(self.min_match)
+    }
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self::EYES
+    }
+}
+
 #[derive(Debug)]
-struct Wheel {
-    spin: u8,
-    slots: Vec<String>,
-    map: Vec<[u8; 2]>,
+pub struct Wheel {
+    pub spin: usize,
+    pub slots: Vec<String>,
+    // Symbol ids of each slot under the machine's scoring
+    symbols: Vec<Vec<usize>>,
+}
+
+/// Best and worst total score over a number of lever pulls.
+pub struct PullReport {
+    pub max: usize,
+    pub min: usize,
+    /// Lever pulled before each spin for the best and worst scores: -1 to push the wheels back one
+    /// slot, 1 to push them forward, 0 to leave them.  Empty unless asked for.
+    pub max_levers: Vec<i8>,
+    pub min_levers: Vec<i8>,
+}
+
+// The levers pulled on one pull to reach each state with its best and worst score so far, two
+// bits each, for the states from `first` on.
+struct LeverLayer {
+    first: usize,
+    packed: Vec<u8>,
 }
 
-impl Wheel {
-    fn new(spin: u8) -> Self {
+impl LeverLayer {
+    const MAX: usize = 0;
+    const MIN: usize = 1;
+
+    fn new(first: usize, len: usize) -> Self {
         Self {
-            spin,
-            slots: Vec::new(),
-            map: Vec::new(),
+            first,
+            packed: vec![0; len.div_ceil(2)],
         }
     }
 
-    fn get(&self, idx: u8) -> (&str, &[u8; 2]) {
-        (&self.slots[idx as usize], &self.map[idx as usize])
+    // Where the two bits for `state` and `which` score are
+    fn bits(&self, state: usize, which: usize) -> (usize, usize) {
+        let idx = state - self.first;
+        (idx / 2, (idx % 2) * 4 + which * 2)
     }
 
-    fn add_slot(&mut self, slot: &str, map: &mut HashMap<char, u8>) {
-        let slot = slot.trim();
-        if slot.is_empty() {
-            return;
-        }
-        self.slots.push(slot.into());
-        let mut chars = slot.chars();
-        let first = chars.next().unwrap();
-        let last = chars.last().unwrap();
-
-        let first = if let Some(first) = map.get(&first) {
-            *first
-        } else {
-            let next = map.len() as u8;
-            map.insert(first, next);
-            next
-        };
-        let last = if let Some(last) = map.get(&last) {
-            *last
-        } else {
-            let next = map.len() as u8;
-            map.insert(last, next);
-            next
-        };
+    fn set(&mut self, state: usize, which: usize, lever: i8) {
+        let (byte, shift) = self.bits(state, which);
+        self.packed[byte] &= !(3 << shift);
+        self.packed[byte] |= ((lever + 1) as u8) << shift;
+    }
 
-        self.map.push([first, last]);
+    fn get(&self, state: usize, which: usize) -> i8 {
+        let (byte, shift) = self.bits(state, which);
+        ((self.packed[byte] >> shift) & 3) as i8 - 1
     }
 }
 
 #[derive(Default)]
 pub struct Day16 {
     wheels: Vec<Wheel>,
+    scoring: Scoring,
 }
 
 impl Day16 {
@@ -60,118 +95,355 @@ impl Day16 {
         Self::default()
     }
 
-    fn sequence(&self, offset: &[u8; 12]) -> String {
-        let mut ans = String::new();
-        for (idx, (wheel, offset)) in self.wheels.iter().zip(offset.iter()).enumerate() {
-            if idx != 0 {
-                ans.push(' ');
+    pub fn from_notes(notes: &[u8]) -> Result<Self, Error> {
+        Self::from_notes_with_width(notes, 3)
+    }
+
+    /// Wheels whose slots are `width` characters wide.  Wheel `i`'s slots start at column
+    /// `i * (width + 1)` of each line, and a slot left blank means that wheel has run out.
+    pub fn from_notes_with_width(notes: &[u8], width: usize) -> Result<Self, Error> {
+        if width == 0 {
+            return Err(Error::InvalidInput("Slots need a width".into()));
+        }
+        let mut day = Self::new();
+        let lines = Lines::from_bufread(notes, LinesOpt::RAW)?;
+        let spins = lines
+            .first()
+            .ok_or_else(|| Error::InvalidInput("No spins".into()))?
+            .split(',')
+            .map(|spin| spin.trim().parse())
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        let stride = width + 1;
+        let rows: Vec<Vec<char>> = lines.iter().skip(1).map(|l| l.chars().collect()).collect();
+        let mut wheels = 0;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.iter().enumerate().filter(|(_, c)| **c != ' ') {
+                if x % stride == width {
+                    return Err(Error::InvalidInput(format!(
+                        "Line {} has {c:?} between two wheels at column {}",
+                        y + 2,
+                        x + 1
+                    )));
+                }
+                wheels = wheels.max(x / stride + 1);
             }
-            let (slot, _) = wheel.get(*offset);
-            ans.push_str(slot);
         }
-        ans
-    }
+        if wheels != spins.len() {
+            return Err(Error::InvalidInput(format!(
+                "{} spins but {} wheels",
+                spins.len(),
+                wheels
+            )));
+        }
 
-    fn score(&self, offset: &[u8; 12]) -> usize {
-        let mut num = [0u8; 64];
-        for (wheel, offset) in self.wheels.iter().zip(offset.iter()) {
-            let (_, map) = wheel.get(*offset);
-            for n in map.iter().copied() {
-                num[n as usize] += 1;
+        for (idx, spin) in spins.iter().enumerate() {
+            let slots: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    (idx * stride..idx * stride + width)
+                        .map(|x| row.get(x).copied().unwrap_or(' '))
+                        .collect::<String>()
+                })
+                .filter(|slot| !slot.trim().is_empty())
+                .collect();
+            if slots.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Wheel {} has no slots",
+                    idx + 1
+                )));
             }
+            day.wheels.push(Wheel {
+                spin: *spin,
+                slots,
+                symbols: Vec::new(),
+            });
         }
+        day.index_symbols();
+        Ok(day)
+    }
 
-        num.iter()
-            .copied()
-            .map(|n| n.saturating_sub(2) as usize)
-            .sum()
+    pub fn with_scoring(mut self, scoring: Scoring) -> Self {
+        self.scoring = scoring;
+        self.index_symbols();
+        self
     }
 
-    fn part1(&mut self) -> Result<helper::RunOutput, Error> {
-        let mut offset = [0u8; 12];
-        for _ in 0..100 {
-            offset
-                .iter_mut()
-                .zip(self.wheels.iter())
-                .for_each(|(offset, wheel)| {
-                    *offset = (*offset + wheel.spin).rem_euclid(wheel.slots.len() as u8)
-                });
+    fn index_symbols(&mut self) {
+        let mut ids: HashMap<char, usize> = HashMap::default();
+        for wheel in self.wheels.iter_mut() {
+            wheel.symbols = wheel
+                .slots
+                .iter()
+                .map(|slot| {
+                    let chars: Vec<char> = slot.chars().collect();
+                    self.scoring
+                        .positions
+                        .iter()
+                        .filter_map(|pos| {
+                            let pos = if *pos < 0 {
+                                chars.len().checked_sub(pos.unsigned_abs())?
+                            } else {
+                                *pos as usize
+                            };
+                            let next = ids.len();
+                            chars.get(pos).map(|c| *ids.entry(*c).or_insert(next))
+                        })
+                        .collect()
+                })
+                .collect();
         }
+    }
 
-        Ok(self.sequence(&offset).into())
+    pub fn wheels(&self) -> &[Wheel] {
+        &self.wheels
     }
 
-    fn part2(&mut self) -> Result<helper::RunOutput, Error> {
-        let mut score = 0;
-        let mut seen = HashMap::default();
-        let mut offset = [0u8; 12];
+    pub fn scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    // Turns every wheel by its spin plus the lever's nudge.
+    fn spin(&self, offsets: &mut [usize], lever: isize) {
+        for (offset, wheel) in offsets.iter_mut().zip(self.wheels.iter()) {
+            let len = wheel.slots.len();
+            *offset = (*offset + wheel.spin % len + lever.rem_euclid(len as isize) as usize) % len;
+        }
+    }
+
+    fn sequence(&self, offsets: &[usize]) -> String {
+        let slots: Vec<&str> = self
+            .wheels
+            .iter()
+            .zip(offsets.iter())
+            .map(|(wheel, offset)| wheel.slots[*offset].as_str())
+            .collect();
+        slots.join(" ")
+    }
+
+    fn score(&self, offsets: &[usize]) -> usize {
+        let mut symbols: Vec<usize> = self
+            .wheels
+            .iter()
+            .zip(offsets.iter())
+            .flat_map(|(wheel, offset)| wheel.symbols[*offset].iter().copied())
+            .collect();
+        symbols.sort_unstable();
+        symbols
+            .chunk_by(|a, b| a == b)
+            .map(|matching| self.scoring.coins(matching.len()))
+            .sum()
+    }
+
+    pub fn part1(&mut self) -> Result<String, Error> {
+        let offsets: Vec<usize> = self
+            .wheels
+            .iter()
+            .map(|wheel| 100 * wheel.spin % wheel.slots.len())
+            .collect();
+        Ok(self.sequence(&offsets))
+    }
+
+    pub fn part2(&mut self) -> Result<usize, Error> {
         const ITERS: usize = 202420242024;
-        seen.insert(offset, (0, 0));
-        let mut spin = 0;
+
+        let mut offsets = vec![0; self.wheels.len()];
+        let mut seen: HashMap<Vec<usize>, (usize, usize)> = HashMap::default();
+        seen.insert(offsets.clone(), (0, 0));
+        let mut score = 0;
+        let mut spins = 0;
         let mut found_cycle = false;
-        while spin < ITERS {
-            offset
-                .iter_mut()
-                .zip(self.wheels.iter())
-                .for_each(|(offset, wheel)| {
-                    *offset = (*offset + wheel.spin).rem_euclid(wheel.slots.len() as u8)
-                });
-            score += self.score(&offset);
+        while spins < ITERS {
+            self.spin(&mut offsets, 0);
+            spins += 1;
+            score += self.score(&offsets);
             if !found_cycle {
-                if let Some((last_spin, last_score)) = seen.insert(offset, (spin, score)) {
-                    let cycle_len = spin - last_spin + 1;
-                    let left = ITERS - spin;
-                    let cycles = left / cycle_len;
+                if let Some((last_spins, last_score)) = seen.insert(offsets.clone(), (spins, score))
+                {
+                    let cycle_len = spins - last_spins;
+                    let cycles = (ITERS - spins) / cycle_len;
                     found_cycle = true;
 
                     score += (score - last_score) * cycles;
-                    spin += cycle_len * cycles;
+                    spins += cycle_len * cycles;
+                }
+            }
+        }
+
+        Ok(score)
+    }
+
+    // Reference implementation for stress testing: scores one full period of the machine (the lcm
+    // of every wheel's own period) and scales it up.
+    pub(crate) fn part2_period(&mut self) -> Result<usize, Error> {
+        const ITERS: usize = 202420242024;
+        let period = self.wheels.iter().fold(1, |period, wheel| {
+            let len = wheel.slots.len();
+            let wheel_period = len / gcd(len, wheel.spin % len);
+            period / gcd(period, wheel_period) * wheel_period
+        });
+
+        let mut offsets = vec![0; self.wheels.len()];
+        let mut scores = Vec::with_capacity(period);
+        for _ in 0..period {
+            self.spin(&mut offsets, 0);
+            scores.push(self.score(&offsets));
+        }
+
+        let per_period: usize = scores.iter().sum();
+        let rest: usize = scores[..ITERS % period].iter().sum();
+        Ok(per_period * (ITERS / period) + rest)
+    }
+
+    /// Every wheel is moved by the same lever before each spin, so after any number of pulls the
+    /// wheels' positions only depend on the net lever movement.  That keeps the states to at most
+    /// 2 * pulls + 1 net movements, or the lcm of the wheel lengths when that is smaller since the
+    /// net movement only matters modulo each wheel's length.  Tracing the levers back takes half a
+    /// byte per state each pull could have reached.
+    pub fn pull_levers(&self, pulls: usize, levers: bool) -> PullReport {
+        let lcm = self.wheels.iter().try_fold(1usize, |lcm, wheel| {
+            let len = wheel.slots.len();
+            (lcm / gcd(lcm, len)).checked_mul(len)
+        });
+        let (size, wrap) = match lcm {
+            Some(lcm) if lcm <= 2 * pulls + 1 => (lcm, true),
+            _ => (2 * pulls + 1, false),
+        };
+        let start = if wrap { 0 } else { pulls };
+        let next = |state: usize, lever: i8| -> usize {
+            if wrap {
+                (state + size).wrapping_add_signed(lever as isize) % size
+            } else {
+                state.wrapping_add_signed(lever as isize)
+            }
+        };
+
+        // Best and worst score for each state, with the lever pulled to reach it from the previous
+        // state on each pull when asked for
+        let mut scores: Vec<Option<(usize, usize)>> = vec![None; size];
+        scores[start] = Some((0, 0));
+        let mut from: Vec<LeverLayer> = Vec::new();
+        let mut offsets = vec![0; self.wheels.len()];
+        for pull in 1..=pulls {
+            let mut next_scores: Vec<Option<(usize, usize)>> = vec![None; size];
+            let mut layer = match (levers, wrap) {
+                (false, _) => LeverLayer::new(0, 0),
+                (true, true) => LeverLayer::new(0, size),
+                (true, false) => LeverLayer::new(start - pull, 2 * pull + 1),
+            };
+            for (state, score) in scores.iter().enumerate() {
+                let Some((min, max)) = score else {
+                    continue;
+                };
+                for lever in [-1i8, 0, 1] {
+                    let to = next(state, lever);
+                    let (better_min, better_max) = match next_scores[to] {
+                        Some((to_min, to_max)) => (*min < to_min, *max > to_max),
+                        None => (true, true),
+                    };
+                    let entry = next_scores[to].get_or_insert((*min, *max));
+                    if better_min {
+                        entry.0 = *min;
+                        if levers {
+                            layer.set(to, LeverLayer::MIN, lever);
+                        }
+                    }
+                    if better_max {
+                        entry.1 = *max;
+                        if levers {
+                            layer.set(to, LeverLayer::MAX, lever);
+                        }
+                    }
+                }
+            }
+
+            for (state, score) in next_scores.iter_mut().enumerate() {
+                let Some((min, max)) = score else {
+                    continue;
+                };
+                let net = state as isize - start as isize;
+                for (offset, wheel) in offsets.iter_mut().zip(self.wheels.iter()) {
+                    let len = wheel.slots.len();
+                    let spun = (pull % len) * (wheel.spin % len);
+                    *offset = (net.rem_euclid(len as isize) as usize + spun) % len;
+                }
+                let gained = self.score(&offsets);
+                *min += gained;
+                *max += gained;
+            }
+
+            scores = next_scores;
+            if levers {
+                from.push(layer);
+            }
+        }
+
+        let mut report = PullReport {
+            max: usize::MIN,
+            min: usize::MAX,
+            max_levers: Vec::new(),
+            min_levers: Vec::new(),
+        };
+        let (mut max_state, mut min_state) = (start, start);
+        for (state, score) in scores.iter().enumerate() {
+            if let Some((min, max)) = score {
+                if *max > report.max {
+                    (report.max, max_state) = (*max, state);
+                }
+                if *min < report.min {
+                    (report.min, min_state) = (*min, state);
                 }
             }
-            spin += 1;
         }
 
-        Ok(score.into())
+        if levers {
+            for (which, state, levers) in [
+                (LeverLayer::MAX, max_state, &mut report.max_levers),
+                (LeverLayer::MIN, min_state, &mut report.min_levers),
+            ] {
+                let mut state = state;
+                for layer in from.iter().rev() {
+                    let lever = layer.get(state, which);
+                    levers.push(lever);
+                    state = next(state, -lever);
+                }
+                levers.reverse();
+            }
+        }
+        report
     }
 
-    fn part3(&mut self) -> Result<helper::RunOutput, Error> {
+    pub fn part3(&mut self) -> Result<(usize, usize), Error> {
+        let report = self.pull_levers(256, false);
+        Ok((report.max, report.min))
+    }
+
+    // Reference implementation for stress testing: keeps every distinct (score, offsets) reached,
+    // which only stays small because so many of them collide.
+    pub(crate) fn part3_states(&mut self) -> Result<String, Error> {
         #[derive(Hash, PartialEq, Eq)]
         struct Work {
             score: usize,
-            offset: [u8; 12],
+            offsets: Vec<usize>,
         }
 
         let mut work_hash = HashSet::default();
         work_hash.insert(Work {
             score: 0,
-            offset: [0u8; 12],
+            offsets: vec![0; self.wheels.len()],
         });
 
         let mut next_hash = HashSet::default();
 
         const ITERS: usize = 256;
         for _ in 0..ITERS {
-            for Work { score, offset } in work_hash.drain() {
-                macro_rules! process {
-                    ($delta:expr) => {{
-                        let mut offset = offset.clone();
-
-                        offset
-                            .iter_mut()
-                            .zip(self.wheels.iter())
-                            .for_each(|(offset, wheel)| {
-                                *offset = (*offset as i8 + $delta + wheel.spin as i8)
-                                    .rem_euclid(wheel.slots.len() as i8)
-                                    as u8
-                            });
-                        let score = score + self.score(&offset);
-                        next_hash.insert(Work { score, offset });
-                    }};
+            for Work { score, offsets } in work_hash.drain() {
+                for lever in [-1, 0, 1] {
+                    let mut offsets = offsets.clone();
+                    self.spin(&mut offsets, lever);
+                    let score = score + self.score(&offsets);
+                    next_hash.insert(Work { score, offsets });
                 }
-
-                process!(-1);
-                process!(0);
-                process!(1);
             }
 
             std::mem::swap(&mut work_hash, &mut next_hash);
@@ -184,36 +456,107 @@ impl Day16 {
             max = max.max(*score);
         }
 
-        Ok(format!("{max} {min}").into())
+        Ok(format!("{max} {min}"))
     }
 }
 
 impl helper::Runner for Day16 {
     fn parse(&mut self, file: &[u8], _part: u8) -> Result<(), Error> {
-        let lines = Lines::from_bufread(file, LinesOpt::RAW)?;
-        for spin in lines[0].split(',') {
-            self.wheels.push(Wheel::new(spin.parse()?));
-        }
-
-        let mut map = HashMap::default();
-        for line in lines[2..].iter() {
-            for (i, wheel) in self.wheels.iter_mut().enumerate() {
-                let idx = i * 4;
-                if idx >= line.len() {
-                    break;
-                }
-                wheel.add_slot(&line[idx..idx + 3], &mut map);
-            }
-        }
+        *self = Self::from_notes(file)?;
         Ok(())
     }
 
     fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
         match part {
-            1 => self.part1(),
-            2 => self.part2(),
-            3 => self.part3(),
+            1 => Ok(self.part1()?.into()),
+            2 => Ok(self.part2()?.into()),
+            3 => {
+                let (max, min) = self.part3()?;
+                Ok(format!("{max} {min}").into())
+            }
             _ => Err(Error::Skipped),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1,2,3\n\n^_^ -.- ^,-\n>.- ^_^ >.<\n-_- -.- >.<\n    -.^ ^_^\n    >.>\n";
+    const EXAMPLE_3: &str =
+        "1,2,3\n\n^_^ -.- ^,-\n>.- ^_^ >.<\n-_- -.- ^.^\n    -.^ >.<\n    >.>\n";
+
+    fn day(notes: &str) -> Day16 {
+        Day16::from_notes(notes.as_bytes()).unwrap()
+    }
+
+    // Coins won pulling `levers` in turn, spinning the wheels one pull at a time
+    fn replay(day: &Day16, levers: &[i8]) -> usize {
+        let mut net = 0;
+        let mut coins = 0;
+        for (pull, lever) in levers.iter().enumerate() {
+            net += *lever as isize;
+            let offsets: Vec<usize> = day
+                .wheels
+                .iter()
+                .map(|wheel| {
+                    let spun = ((pull + 1) * wheel.spin) as isize;
+                    (net + spun).rem_euclid(wheel.slots.len() as isize) as usize
+                })
+                .collect();
+            coins += day.score(&offsets);
+        }
+        coins
+    }
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(day(EXAMPLE).part1().unwrap(), ">.- -.- ^,-");
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(day(EXAMPLE).part2().unwrap(), 280014668134);
+        assert_eq!(day(EXAMPLE).part2_period().unwrap(), 280014668134);
+    }
+
+    #[test]
+    fn lever_examples() {
+        let day = day(EXAMPLE_3);
+        for (pulls, max, min) in [
+            (1, 4, 1),
+            (2, 6, 1),
+            (3, 9, 2),
+            (10, 26, 5),
+            (100, 246, 50),
+            (256, 627, 128),
+            (1000, 2446, 500),
+            (2024, 4948, 1012),
+        ] {
+            let report = day.pull_levers(pulls, true);
+            assert_eq!((report.max, report.min), (max, min), "{pulls} pulls");
+            assert_eq!(report.max_levers.len(), pulls);
+            assert_eq!(replay(&day, &report.max_levers), max);
+            assert_eq!(replay(&day, &report.min_levers), min);
+        }
+    }
+
+    #[test]
+    fn wheels_at_a_fixed_stride() {
+        // The second wheel's slots start with a space, which is kept
+        let day = day("1,1\n\n^_^  _^\n>.<  .>\n");
+        assert_eq!(day.wheels[1].slots, [" _^", " .>"]);
+
+        let day = Day16::from_notes_with_width(b"1,2\n\n^__^ -..-\n>__< \n", 4).unwrap();
+        assert_eq!(day.wheels[0].slots, ["^__^", ">__<"]);
+        assert_eq!(day.wheels[1].slots, ["-..-"]);
+
+        for notes in ["1,2\n\n^_^x-.-\n", "1\n\n^_^ -.-\n", "1,2\n\n^_^\n"] {
+            assert!(matches!(
+                Day16::from_notes(notes.as_bytes()),
+                Err(Error::InvalidInput(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use super::Rng;
+
+// A line of creatures and empty spots, a multiple of six long so every part fights whole groups.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const CREATURES: &[char] = &['A', 'B', 'C', 'D', 'x'];
+
+    let mut notes: String = (0..rng.range(1..=size.max(1)) * 6)
+        .map(|_| *rng.pick(CREATURES))
+        .collect();
+    notes.push('\n');
+    notes
+}
@@ -0,0 +1,23 @@
+use super::Rng;
+
+// Rune words and an inscription grid over a small alphabet so words overlap and wrap often.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const RUNES: &[char] = &['A', 'B', 'C', 'D'];
+    let size = size.max(1);
+
+    let words: Vec<String> = (0..rng.range(1..=4))
+        .map(|_| {
+            (0..rng.range(1..=size.min(5)))
+                .map(|_| *rng.pick(RUNES))
+                .collect()
+        })
+        .collect();
+
+    let mut notes = format!("WORDS:{}\n\n", words.join(","));
+    let width = rng.range(1..=size * 2);
+    for _ in 0..rng.range(1..=size) {
+        notes.extend((0..width).map(|_| *rng.pick(RUNES)));
+        notes.push('\n');
+    }
+    notes
+}
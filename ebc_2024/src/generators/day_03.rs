@@ -0,0 +1,22 @@
+use super::Rng;
+
+// Overlapping rectangles of diggable ground, some touching the edge of the map.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let (width, height) = (rng.range(1..=size * 3), rng.range(1..=size * 3));
+    let mut map = vec![vec!['.'; width]; height];
+    for _ in 0..rng.range(1..=size) {
+        let (x, y) = (rng.below(width), rng.below(height));
+        let (w, h) = (rng.range(1..=width - x), rng.range(1..=height - y));
+        for row in map[y..y + h].iter_mut() {
+            row[x..x + w].fill('#');
+        }
+    }
+
+    let mut notes = String::new();
+    for row in map.iter() {
+        notes.extend(row.iter());
+        notes.push('\n');
+    }
+    notes
+}
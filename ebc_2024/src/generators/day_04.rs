@@ -0,0 +1,13 @@
+use super::Rng;
+
+// Nail heights, bunched up at times so several levels tie.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let spread = if rng.chance(50) { 5 } else { size * 100 };
+
+    let mut notes = String::new();
+    for _ in 0..rng.range(1..=size * 2) {
+        notes.push_str(&format!("{}\n", rng.range(0..=spread)));
+    }
+    notes
+}
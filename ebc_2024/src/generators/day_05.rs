@@ -0,0 +1,19 @@
+use super::Rng;
+
+// Two to four rows of clappers across a few columns, with numbers both small enough and large
+// enough to go round the columns.  Two rows keep any column the dance moves into from being empty,
+// and more than four make some dances take too long to repeat.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let columns = rng.range(2..=4);
+
+    let mut notes = String::new();
+    for _ in 0..rng.range(2..=size.min(3) + 1) {
+        let row: Vec<String> = (0..columns)
+            .map(|_| rng.range(1..=size * 4).to_string())
+            .collect();
+        notes.push_str(&row.join(" "));
+        notes.push('\n');
+    }
+    notes
+}
@@ -0,0 +1,54 @@
+use super::Rng;
+
+// A random tree with exactly one fruit at a depth no other fruit shares.  Sometimes a pair of
+// pests point at each other, forming a cycle that never reaches the root.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut names = vec!["RR".to_string()];
+    let mut depths = vec![0];
+    let mut children: Vec<Vec<String>> = vec![Vec::new()];
+    for _ in 0..rng.range(1..=size.max(1) * 2) {
+        let name = loop {
+            let name: String = (0..rng.range(2..=4))
+                .map(|_| (b'A' + rng.below(26) as u8) as char)
+                .collect();
+            if !names.contains(&name) && name != "ANT" && name != "BUG" {
+                break name;
+            }
+        };
+        let parent = rng.below(names.len());
+        children[parent].push(name.clone());
+        names.push(name);
+        depths.push(depths[parent] + 1);
+        children.push(Vec::new());
+    }
+
+    // The powerful fruit, then groups of at least two fruits on every other depth used
+    let powerful = rng.below(names.len());
+    children[powerful].push("@".into());
+    let max_depth = *depths.iter().max().unwrap();
+    for depth in 0..=max_depth {
+        if depth == depths[powerful] || !rng.chance(50) {
+            continue;
+        }
+        let branches: Vec<usize> = (0..names.len()).filter(|b| depths[*b] == depth).collect();
+        for _ in 0..rng.range(2..=3) {
+            children[*rng.pick(&branches)].push("@".into());
+        }
+    }
+
+    let mut lines: Vec<String> = names
+        .iter()
+        .zip(children.iter())
+        .filter(|(_, children)| !children.is_empty())
+        .map(|(name, children)| format!("{name}:{}", children.join(",")))
+        .collect();
+    if rng.chance(25) {
+        lines.push("ANT:BUG".into());
+        lines.push("BUG:ANT,@".into());
+    }
+    rng.shuffle(&mut lines);
+
+    let mut notes = lines.join("\n");
+    notes.push('\n');
+    notes
+}
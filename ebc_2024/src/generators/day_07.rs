@@ -0,0 +1,106 @@
+use super::Rng;
+
+const ACTIONS: &[char] = &['+', '-', '='];
+
+// Devices with random plans racing on one or two tracks side by side.  A track is either a
+// rectangular loop or a solid patch of track, which still makes a single loop with every piece of
+// it running alongside others.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+
+    let mut notes = String::new();
+    for device in 0..rng.range(1..=size.min(6)) {
+        let plan: Vec<String> = (0..rng.range(1..=size))
+            .map(|_| rng.pick(ACTIONS).to_string())
+            .collect();
+        notes.push_str(&format!(
+            "{}:{}\n",
+            (b'A' + device as u8) as char,
+            plan.join(",")
+        ));
+    }
+
+    let mut map: Vec<Vec<char>> = Vec::new();
+    for _ in 0..rng.range(1..=2) {
+        let track = if rng.chance(50) {
+            rectangle(rng, size)
+        } else {
+            patch(rng, size)
+        };
+        // Next to the tracks so far, with a gap between them
+        let left = map
+            .iter()
+            .map(|row| row.len())
+            .max()
+            .map_or(0, |width| width + 1);
+        map.resize(map.len().max(track.len()), Vec::new());
+        for (row, track) in map.iter_mut().zip(track) {
+            row.resize(left, ' ');
+            row.extend(track);
+        }
+    }
+
+    notes.push('\n');
+    for row in map {
+        notes.push_str(row.iter().collect::<String>().trim_end());
+        notes.push('\n');
+    }
+    notes
+}
+
+// A rectangular loop with `S` in the top left corner.
+fn rectangle(rng: &mut Rng, size: usize) -> Vec<Vec<char>> {
+    let width = rng.range(3..=size + 3);
+    let height = rng.range(2..=size / 2 + 2);
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    if x == 0 && y == 0 {
+                        'S'
+                    } else if y == 0 || y == height - 1 || x == 0 || x == width - 1 {
+                        *rng.pick(ACTIONS)
+                    } else {
+                        ' '
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Every cell of some 2x2 blocks grown out from the top left one, each block next to one grown
+// earlier.  Going round the tree of blocks visits every cell once, so the patch is one loop, and
+// `S` goes in the top left corner.
+fn patch(rng: &mut Rng, size: usize) -> Vec<Vec<char>> {
+    let (width, height) = (
+        rng.range(1..=size.div_ceil(2)),
+        rng.range(1..=size.div_ceil(2)),
+    );
+    let mut blocks = vec![vec![false; width]; height];
+    blocks[0][0] = true;
+    let mut grown = vec![(0, 0)];
+    for _ in 0..rng.range(1..=width * height) {
+        let (x, y): (usize, usize) = *rng.pick(&grown);
+        let (dx, dy) = *rng.pick(&[(1, 0), (0, 1), (-1, 0), (0, -1)]);
+        let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+            continue;
+        };
+        if x < width && y < height && !blocks[y][x] {
+            blocks[y][x] = true;
+            grown.push((x, y));
+        }
+    }
+
+    let mut map = vec![vec![' '; width * 2]; height * 2];
+    for (x, y) in grown {
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            map[y * 2 + dy][x * 2 + dx] = *rng.pick(ACTIONS);
+        }
+    }
+    map[0][0] = 'S';
+    while map.last().is_some_and(|row| row.iter().all(|c| *c == ' ')) {
+        map.pop();
+    }
+    map
+}
@@ -0,0 +1,74 @@
+use super::Rng;
+
+// Either a map of targets stacked in columns, every one of them in reach of a catapult, or a list
+// of meteors each built backwards from a shot that hits it.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    if rng.chance(50) {
+        map(rng, size)
+    } else {
+        meteors(rng, size)
+    }
+}
+
+fn map(rng: &mut Rng, size: usize) -> String {
+    let width = rng.range(8..=size.max(3) * 4);
+    let height = rng.range(5..=size.max(3) + 3);
+    let mut map = vec![vec!['.'; width]; height];
+    map[height - 1].fill('=');
+    // A, B and C stacked from just above the ground
+    for (idx, c) in ['A', 'B', 'C'].into_iter().enumerate() {
+        map[height - 2 - idx][1] = c;
+    }
+
+    // A catapult hits (x, y) when the distance across less the drop below it is a multiple of 3
+    let hittable = |x: usize, y: usize| {
+        (0..3).any(|idx| {
+            let (dx, dy) = (x as isize - 1, y as isize - (height - 2 - idx) as isize);
+            dx > 0 && dx - dy >= 3 && (dx - dy) % 3 == 0
+        })
+    };
+    let mut targets = Vec::new();
+    for x in 3..width {
+        if !rng.chance(40) {
+            continue;
+        }
+        let stack = rng.range(1..=(height - 2).min(3));
+        let cells: Vec<usize> = (0..stack).map(|level| height - 2 - level).collect();
+        if cells.iter().all(|y| hittable(x, *y)) {
+            for y in cells {
+                targets.push((x, y, if rng.chance(25) { 'H' } else { 'T' }));
+            }
+        }
+    }
+    for (x, y, c) in targets {
+        map[y][x] = c;
+    }
+
+    let mut notes = String::new();
+    for row in map {
+        notes.extend(row);
+        notes.push('\n');
+    }
+    notes
+}
+
+fn meteors(rng: &mut Rng, size: usize) -> String {
+    let mut notes = String::new();
+    for _ in 0..rng.range(1..=size.max(1)) {
+        // A shot from the catapult at `height`, fired after `delay` steps and flying `flight`
+        // steps at `power`, meeting the meteor as it falls one left and one down a step
+        let height = rng.below(3) as isize;
+        let power = rng.range(1..=size.max(1) * 2) as isize;
+        let flight = rng.range(1..=power as usize * 3) as isize;
+        let delay = rng.range(0..=size * 2) as isize;
+        let shot_height = height
+            + match flight {
+                t if t <= power => t,
+                t if t <= 2 * power => power,
+                t => 3 * power - t,
+            };
+        let time = delay + flight;
+        notes.push_str(&format!("{} {}\n", flight + time, shot_height + time));
+    }
+    notes
+}
@@ -0,0 +1,46 @@
+use super::Rng;
+
+// Platforms at random levels with some walls and gaps, one or more S and E, and sometimes a few
+// pairs of teleport pads.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let (width, height) = (rng.range(2..=size * 2 + 1), rng.range(1..=size + 1));
+    let mut map: Vec<Vec<char>> = (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| match rng.below(10) {
+                    0 => '#',
+                    1 => ' ',
+                    _ => char::from_digit(rng.below(10) as u32, 10).unwrap(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut cells: Vec<(usize, usize)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .collect();
+    rng.shuffle(&mut cells);
+    let mut cells = cells.into_iter();
+    for c in ['S', 'E'] {
+        for _ in 0..rng.range(1..=2) {
+            if let Some((x, y)) = cells.next() {
+                map[y][x] = c;
+            }
+        }
+    }
+    for pad in ['a', 'b'].into_iter().take(rng.below(3)) {
+        for _ in 0..2 {
+            if let Some((x, y)) = cells.next() {
+                map[y][x] = pad;
+            }
+        }
+    }
+
+    let mut notes = String::new();
+    for row in map.iter() {
+        notes.extend(row.iter());
+        notes.push('\n');
+    }
+    notes
+}
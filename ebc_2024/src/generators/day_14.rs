@@ -0,0 +1,20 @@
+use super::Rng;
+
+// Branches which mostly start up the trunk and then wander, often doubling back over themselves
+// or each other so the tree has overlaps and loops.  Now and then a branch stays on the trunk.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const TURNS: &[char] = &['U', 'D', 'L', 'R', 'F', 'B'];
+
+    let mut notes = String::new();
+    for _ in 0..rng.range(1..=size.max(1) * 2) {
+        let mut steps = vec![format!("U{}", rng.range(1..=size.max(1) * 3))];
+        if !rng.chance(10) {
+            for _ in 0..rng.range(1..=size.max(1)) {
+                steps.push(format!("{}{}", rng.pick(TURNS), rng.range(1..=size.max(2))));
+            }
+        }
+        notes.push_str(&steps.join(","));
+        notes.push('\n');
+    }
+    notes
+}
@@ -0,0 +1,67 @@
+use super::Rng;
+
+// A maze carved from a random spanning tree with a few extra openings, an entrance in the top
+// wall, and every herb kind placed at least once.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let cols = rng.range(2..=size.max(2));
+    let rows = rng.range(2..=size.max(2));
+    let (width, height) = (cols * 2 + 1, rows * 2 + 1);
+    let mut map = vec![vec!['#'; width]; height];
+
+    let mut visited = vec![vec![false; cols]; rows];
+    let mut stack = vec![(rng.below(cols), rng.below(rows))];
+    visited[stack[0].1][stack[0].0] = true;
+    map[stack[0].1 * 2 + 1][stack[0].0 * 2 + 1] = '.';
+    while let Some(&(x, y)) = stack.last() {
+        let mut next: Vec<(usize, usize)> = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ]
+        .into_iter()
+        .filter(|(nx, ny)| *nx < cols && *ny < rows && !visited[*ny][*nx])
+        .collect();
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+        rng.shuffle(&mut next);
+        let (nx, ny) = next[0];
+        visited[ny][nx] = true;
+        map[ny * 2 + 1][nx * 2 + 1] = '.';
+        map[y + ny + 1][x + nx + 1] = '.';
+        stack.push((nx, ny));
+    }
+
+    // Extra openings so there is more than one way around
+    for (y, row) in map.iter_mut().enumerate().take(height - 1).skip(1) {
+        for (x, c) in row.iter_mut().enumerate().take(width - 1).skip(1) {
+            if *c == '#' && (x + y) % 2 == 1 && rng.chance(15) {
+                *c = '.';
+            }
+        }
+    }
+
+    let entrance = rng.below(cols) * 2 + 1;
+    map[0][entrance] = '.';
+
+    let mut open: Vec<(usize, usize)> = (1..height - 1)
+        .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+        .filter(|(x, y)| map[*y][*x] == '.' && (*x, *y) != (entrance, 1))
+        .collect();
+    rng.shuffle(&mut open);
+    let kinds = rng.range(1..=5.min(open.len()));
+    let herbs = rng.range(kinds..=(kinds * 3).min(open.len()));
+    for (idx, (x, y)) in open.into_iter().take(herbs).enumerate() {
+        let kind = if idx < kinds { idx } else { rng.below(kinds) };
+        map[y][x] = (b'A' + kind as u8) as char;
+    }
+
+    let mut notes = String::new();
+    for row in map.iter() {
+        notes.extend(row.iter());
+        notes.push('\n');
+    }
+    notes
+}
@@ -0,0 +1,41 @@
+use super::Rng;
+
+// Wheels of three character cat faces laid out in columns, with shorter wheels leaving gaps.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const EYES: &[char] = &['^', '-', 'o', '*', '>', '<'];
+    const MOUTHS: &[char] = &['_', '.', ','];
+
+    let wheels: Vec<(usize, Vec<String>)> = (0..rng.range(1..=size.clamp(1, 6)))
+        .map(|_| {
+            let spin = rng.range(1..=9);
+            let faces = (0..rng.range(1..=size.max(1) + 2))
+                .map(|_| {
+                    let mut face = String::new();
+                    face.push(*rng.pick(EYES));
+                    face.push(*rng.pick(MOUTHS));
+                    face.push(*rng.pick(EYES));
+                    face
+                })
+                .collect();
+            (spin, faces)
+        })
+        .collect();
+
+    let spins: Vec<String> = wheels.iter().map(|(spin, _)| spin.to_string()).collect();
+    let mut notes = format!("{}\n\n", spins.join(","));
+    let rows = wheels.iter().map(|(_, faces)| faces.len()).max().unwrap();
+    for row in 0..rows {
+        let line: Vec<String> = wheels
+            .iter()
+            .map(|(_, faces)| {
+                faces
+                    .get(row)
+                    .cloned()
+                    .unwrap_or_else(|| " ".repeat(faces[0].len()))
+            })
+            .collect();
+        notes.push_str(line.join(" ").trim_end());
+        notes.push('\n');
+    }
+    notes
+}
@@ -0,0 +1,99 @@
+//! Random but well formed quest notes, used to stress test the solvers against their reference
+//! implementations.
+use std::ops::RangeInclusive;
+
+mod day_01;
+mod day_02;
+mod day_03;
+mod day_04;
+mod day_05;
+mod day_06;
+mod day_07;
+mod day_12;
+mod day_13;
+mod day_14;
+mod day_15;
+mod day_16;
+
+pub type Generator = fn(&mut Rng, usize) -> String;
+
+// How many notes to check each part on by default, a lot fewer where a reference
+// implementation takes seconds per input
+const ITERATIONS: usize = 1000;
+const SLOW: usize = 10;
+
+static GENERATORS: &[(usize, usize, Generator, [usize; 3])] = &[
+    (2024, 1, day_01::generate, [ITERATIONS; 3]),
+    (2024, 2, day_02::generate, [ITERATIONS; 3]),
+    (2024, 3, day_03::generate, [ITERATIONS; 3]),
+    (2024, 4, day_04::generate, [ITERATIONS; 3]),
+    (2024, 5, day_05::generate, [ITERATIONS; 3]),
+    (2024, 6, day_06::generate, [ITERATIONS; 3]),
+    (2024, 7, day_07::generate, [ITERATIONS, ITERATIONS, SLOW]),
+    (2024, 12, day_12::generate, [ITERATIONS; 3]),
+    (2024, 13, day_13::generate, [ITERATIONS; 3]),
+    (2024, 14, day_14::generate, [ITERATIONS; 3]),
+    (2024, 15, day_15::generate, [ITERATIONS; 3]),
+    (2024, 16, day_16::generate, [ITERATIONS, ITERATIONS, SLOW]),
+];
+
+/// Generator for a quest's notes.  `size` roughly scales the dimensions of the generated notes.
+pub fn generator(year: usize, quest: usize) -> Option<Generator> {
+    GENERATORS
+        .iter()
+        .find(|(y, q, _, _)| *y == year && *q == quest)
+        .map(|(_, _, generator, _)| *generator)
+}
+
+/// How many generated notes a part is checked on unless told otherwise.
+pub fn iterations(year: usize, quest: usize, part: u8) -> usize {
+    GENERATORS
+        .iter()
+        .find(|(y, q, _, _)| *y == year && *q == quest)
+        .and_then(|(_, _, _, iterations)| iterations.get((part as usize).checked_sub(1)?))
+        .copied()
+        .unwrap_or(ITERATIONS)
+}
+
+pub fn generated_quests() -> impl Iterator<Item = (usize, usize)> {
+    GENERATORS.iter().map(|(year, quest, _, _)| (*year, *quest))
+}
+
+/// Small xorshift generator so notes can be reproduced from a seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<usize>) -> usize {
+        range.start() + self.below(range.end() - range.start() + 1)
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::num::NonZeroU32;
+
+const NONE: usize = usize::MAX;
+
+/// A map of chars as a weighted graph between orthogonally adjacent cells.  Cells are numbered in
+/// reading order, and what a step between two cells costs comes from the chars on either side.
+#[derive(Clone, Default)]
+pub struct GridGraph {
+    width: usize,
+    cells: Vec<char>,
+    // What stepping left, right, up and down from every cell costs, if it can be done
+    costs: Vec<[Option<NonZeroU32>; 4]>,
+    // Extra one way links between cells which aren't adjacent as (from, to, cost), sorted by from
+    links: Vec<(usize, usize, u32)>,
+    // Every step costs 1, so searches can go breadth first
+    unit: bool,
+}
+
+impl GridGraph {
+    /// `cost(from, to)` is the cost of stepping from a cell holding `from` to a neighbour holding
+    /// `to`, or `None` if that step can't be taken.  Short rows are padded with spaces.
+    ///
+    /// Panics if a step costs 0.
+    pub fn new<F>(rows: &[Vec<char>], cost: F) -> Self
+    where
+        F: Fn(char, char) -> Option<u32>,
+    {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut cells = vec![' '; width * rows.len()];
+        for (y, row) in rows.iter().enumerate() {
+            cells[y * width..y * width + row.len()].copy_from_slice(row);
+        }
+
+        let mut graph = Self {
+            width,
+            cells,
+            costs: Vec::new(),
+            links: Vec::new(),
+            unit: true,
+        };
+        graph.costs = (0..graph.cells.len())
+            .map(|from| {
+                std::array::from_fn(|dir| {
+                    graph
+                        .beside(from, dir)
+                        .and_then(|to| cost(graph.cells[from], graph.cells[to]))
+                        .map(|cost| NonZeroU32::new(cost).expect("Steps cost at least 1"))
+                })
+            })
+            .collect();
+        graph.unit = graph
+            .costs
+            .iter()
+            .flatten()
+            .flatten()
+            .all(|cost| cost.get() == 1);
+        graph
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn cell(&self, cell: usize) -> char {
+        self.cells[cell]
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height()).then_some(y * self.width + x)
+    }
+
+    pub fn xy(&self, cell: usize) -> (usize, usize) {
+        (cell % self.width, cell / self.width)
+    }
+
+    // The cell next to `cell` going left, right, up or down.
+    fn beside(&self, cell: usize, dir: usize) -> Option<usize> {
+        match dir {
+            0 => (!cell.is_multiple_of(self.width)).then(|| cell - 1),
+            1 => (cell % self.width + 1 < self.width).then(|| cell + 1),
+            2 => cell.checked_sub(self.width),
+            _ => Some(cell + self.width).filter(|cell| *cell < self.cells.len()),
+        }
+    }
+
+    /// Where stepping left, right, up or down (`dir` 0 to 3) from `cell` leads and what it
+    /// costs, if that step can be taken.
+    pub fn step(&self, cell: usize, dir: usize) -> Option<(usize, usize)> {
+        let cost = self.costs[cell][dir]?;
+        Some((self.beside(cell, dir)?, cost.get() as usize))
+    }
+
+    /// Adds a one way link from `from` to `to` costing `cost` on top of the steps between
+    /// adjacent cells.  Panics if `cost` is 0.
+    pub fn link(&mut self, from: usize, to: usize, cost: u32) {
+        assert!(cost >= 1, "Links cost at least 1");
+        let idx = self.links.partition_point(|link| link.0 <= from);
+        self.links.insert(idx, (from, to, cost));
+        self.unit &= cost == 1;
+    }
+
+    fn links_from(&self, cell: usize) -> &[(usize, usize, u32)] {
+        let start = self.links.partition_point(|link| link.0 < cell);
+        let len = self.links[start..].partition_point(|link| link.0 == cell);
+        &self.links[start..start + len]
+    }
+
+    /// Every cell holding `c`, in reading order.
+    pub fn find(&self, c: char) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|cell| self.cells[*cell] == c)
+            .collect()
+    }
+
+    /// The cells `cell` can step or link to, each with the cost of getting there.
+    pub fn neighbors(&self, cell: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..4).filter_map(move |dir| self.step(cell, dir)).chain(
+            self.links_from(cell)
+                .iter()
+                .map(|(_, to, cost)| (*to, *cost as usize)),
+        )
+    }
+
+    /// Cheapest paths from the nearest of `sources` to every cell that can be reached.
+    pub fn paths_from(&self, sources: &[usize]) -> Paths<'_> {
+        self.search(sources, None, |_| false)
+    }
+
+    /// Like `paths_from`, but only stepping on cells where `allowed` is set.
+    pub fn paths_within(&self, sources: &[usize], allowed: &[bool]) -> Paths<'_> {
+        self.search(sources, Some(allowed), |_| false)
+    }
+
+    /// The cheapest path from any of `sources` to any cell where `is_target` holds.
+    pub fn nearest<T>(&self, sources: &[usize], is_target: T) -> Option<Path>
+    where
+        T: Fn(usize) -> bool,
+    {
+        let paths = self.search(sources, None, is_target);
+        let target = paths.reached?;
+        Some(Path {
+            cost: paths.cost(target)?,
+            cells: paths.path(target)?,
+        })
+    }
+
+    /// Searches outwards from `sources` until a cell where `is_target` holds is settled, or
+    /// everything reachable is.
+    pub fn search<T>(&self, sources: &[usize], allowed: Option<&[bool]>, is_target: T) -> Paths<'_>
+    where
+        T: Fn(usize) -> bool,
+    {
+        let allowed = |cell: usize| allowed.is_none_or(|allowed| allowed[cell]);
+        let mut paths = Paths {
+            graph: self,
+            dist: vec![NONE; self.cells.len()],
+            reached: None,
+        };
+
+        if self.unit {
+            let mut work = VecDeque::new();
+            for source in sources.iter().copied().filter(|cell| allowed(*cell)) {
+                if paths.dist[source] == NONE {
+                    paths.dist[source] = 0;
+                    work.push_back(source);
+                }
+            }
+            while let Some(at) = work.pop_front() {
+                if is_target(at) {
+                    paths.reached = Some(at);
+                    break;
+                }
+                for (next, _) in self.neighbors(at) {
+                    if allowed(next) && paths.dist[next] == NONE {
+                        paths.dist[next] = paths.dist[at] + 1;
+                        work.push_back(next);
+                    }
+                }
+            }
+        } else {
+            let mut work = BinaryHeap::new();
+            for source in sources.iter().copied().filter(|cell| allowed(*cell)) {
+                paths.dist[source] = 0;
+                work.push(Reverse((0, source)));
+            }
+            while let Some(Reverse((dist, at))) = work.pop() {
+                if dist > paths.dist[at] {
+                    continue;
+                }
+                if is_target(at) {
+                    paths.reached = Some(at);
+                    break;
+                }
+                for (next, cost) in self.neighbors(at) {
+                    if allowed(next) && dist + cost < paths.dist[next] {
+                        paths.dist[next] = dist + cost;
+                        work.push(Reverse((dist + cost, next)));
+                    }
+                }
+            }
+        }
+        paths
+    }
+}
+
+/// The result of a search: the cost of reaching each cell.  Costs of cells past the target of a
+/// search that stopped early are only upper bounds, but still the cost of a real path.
+pub struct Paths<'a> {
+    graph: &'a GridGraph,
+    dist: Vec<usize>,
+    /// The target the search stopped at, if it had one and got there
+    pub reached: Option<usize>,
+}
+
+impl Paths<'_> {
+    pub fn cost(&self, cell: usize) -> Option<usize> {
+        (self.dist[cell] != NONE).then_some(self.dist[cell])
+    }
+
+    /// The cells from the source the path starts at up to and including `to`.  Each step back
+    /// goes to the first cell (left, right, up, down, then links) which gets to it for exactly
+    /// the difference in cost.
+    pub fn path(&self, to: usize) -> Option<Vec<usize>> {
+        self.cost(to)?;
+        let mut path = vec![to];
+        let mut at = to;
+        while self.dist[at] != 0 {
+            let steps = (0..4).filter_map(|dir| {
+                let prev = self.graph.beside(at, dir)?;
+                Some((prev, self.graph.costs[prev][dir ^ 1]?.get()))
+            });
+            let links = self.graph.links.iter().filter(|link| link.1 == at);
+            at = steps
+                .chain(links.map(|(from, _, cost)| (*from, *cost)))
+                .find(|(prev, cost)| {
+                    self.dist[*prev] != NONE && self.dist[*prev] + *cost as usize == self.dist[at]
+                })?
+                .0;
+            path.push(at);
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A cheapest path, from its source up to and including its target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    pub cost: usize,
+    pub cells: Vec<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::Rng;
+
+    fn rows(map: &str) -> Vec<Vec<char>> {
+        map.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    // Stepping onto a digit costs that much, anything else but a wall costs 1
+    fn weighted(map: &str) -> GridGraph {
+        GridGraph::new(&rows(map), |_, to| match to {
+            '#' | ' ' => None,
+            c => Some(c.to_digit(10).unwrap_or(1)),
+        })
+    }
+
+    #[test]
+    fn paths_go_round_costly_cells() {
+        let graph = weighted("S91\n1#1\n111\n");
+        assert!(!graph.unit);
+        let paths = graph.paths_from(&graph.find('S'));
+        let to = graph.index(2, 0).unwrap();
+        assert_eq!(paths.cost(to), Some(6));
+        let path: Vec<_> = paths
+            .path(to)
+            .unwrap()
+            .into_iter()
+            .map(|c| graph.xy(c))
+            .collect();
+        assert_eq!(
+            path,
+            [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)]
+        );
+        assert_eq!(paths.cost(graph.index(1, 1).unwrap()), None);
+        assert_eq!(paths.path(graph.index(1, 1).unwrap()), None);
+    }
+
+    #[test]
+    fn links_reach_past_walls() {
+        let mut graph = weighted("S.#.E\n");
+        let is_end = |cell| graph.cell(cell) == 'E';
+        assert_eq!(graph.nearest(&[0], is_end), None);
+
+        graph.link(1, 3, 1);
+        assert!(graph.unit);
+        let path = graph.nearest(&[0], |cell| graph.cell(cell) == 'E').unwrap();
+        assert_eq!(
+            path,
+            Path {
+                cost: 3,
+                cells: vec![0, 1, 3, 4]
+            }
+        );
+
+        // Links only go one way, and a costly one leaves breadth first search behind
+        graph.link(3, 0, 5);
+        assert!(!graph.unit);
+        let paths = graph.paths_from(&[4]);
+        assert_eq!(paths.cost(0), Some(6));
+        assert_eq!(paths.path(0), Some(vec![4, 3, 0]));
+        assert_eq!(paths.cost(2), None);
+    }
+
+    #[test]
+    fn paths_within_keep_to_allowed_cells() {
+        let graph = weighted("...\n...\n...\n");
+        let mut allowed = vec![true; graph.len()];
+        allowed[1] = false;
+        allowed[4] = false;
+        let paths = graph.paths_within(&[0], &allowed);
+        assert_eq!(paths.cost(2), Some(6));
+        assert_eq!(paths.cost(4), None);
+        assert_eq!(paths.path(2), Some(vec![0, 3, 6, 7, 8, 5, 2]));
+
+        // Sources which aren't allowed don't start anything
+        let paths = graph.paths_within(&[1], &allowed);
+        assert!((0..graph.len()).all(|cell| paths.cost(cell).is_none()));
+    }
+
+    #[test]
+    fn breadth_first_matches_dijkstra() {
+        let mut rng = Rng::new(44);
+        for _ in 0..50 {
+            let map: Vec<Vec<char>> = (0..rng.range(1..=12))
+                .map(|_| {
+                    (0..rng.range(1..=12))
+                        .map(|_| if rng.chance(30) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            let mut graph =
+                GridGraph::new(&map, |from, to| (from != '#' && to != '#').then_some(1));
+            for _ in 0..rng.range(0..=3) {
+                let (from, to) = (rng.below(graph.len()), rng.below(graph.len()));
+                graph.link(from, to, 1);
+            }
+            assert!(graph.unit);
+            let sources = [rng.below(graph.len())];
+            let paths = graph.paths_from(&sources);
+            let bfs: Vec<_> = (0..graph.len()).map(|cell| paths.cost(cell)).collect();
+
+            let dijkstra = GridGraph {
+                unit: false,
+                ..graph.clone()
+            };
+            let paths = dijkstra.paths_from(&sources);
+            for (cell, cost) in bfs.iter().enumerate() {
+                assert_eq!(paths.cost(cell), *cost);
+                if let Some(path) = paths.path(cell) {
+                    assert_eq!(path.len() - 1, cost.unwrap());
+                    assert_eq!(path[0], sources[0]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn steps_cost_something() {
+        weighted("S0\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1")]
+    fn links_cost_something() {
+        weighted("S.\n").link(0, 1, 0);
+    }
+}
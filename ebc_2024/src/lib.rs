@@ -1,46 +1,252 @@
 use helper::NewRunner;
 use std::collections::BTreeMap;
 
-mod day_01;
-mod day_02;
-mod day_03;
-mod day_04;
-mod day_05;
-mod day_06;
-mod day_07;
+// Solved quests have public modules with `from_notes` and typed parts, the ones not solved yet
+// stay private behind their runners.
+
+#[cfg(feature = "quest-01")]
+pub mod day_01;
+#[cfg(feature = "quest-02")]
+pub mod day_02;
+#[cfg(feature = "quest-03")]
+pub mod day_03;
+#[cfg(feature = "quest-04")]
+pub mod day_04;
+#[cfg(feature = "quest-05")]
+pub mod day_05;
+#[cfg(feature = "quest-06")]
+pub mod day_06;
+#[cfg(feature = "quest-07")]
+pub mod day_07;
+#[cfg(feature = "quest-08")]
 mod day_08;
+#[cfg(feature = "quest-09")]
 mod day_09;
+#[cfg(feature = "quest-10")]
 mod day_10;
+#[cfg(feature = "quest-11")]
 mod day_11;
-mod day_12;
-mod day_13;
-mod day_14;
-mod day_15;
-mod day_16;
+#[cfg(feature = "quest-12")]
+pub mod day_12;
+#[cfg(feature = "quest-13")]
+pub mod day_13;
+#[cfg(feature = "quest-14")]
+pub mod day_14;
+#[cfg(feature = "quest-15")]
+pub mod day_15;
+#[cfg(feature = "quest-16")]
+pub mod day_16;
+#[cfg(feature = "quest-17")]
 mod day_17;
+#[cfg(feature = "quest-18")]
 mod day_18;
+#[cfg(feature = "quest-19")]
 mod day_19;
+#[cfg(feature = "quest-20")]
 mod day_20;
+pub mod generators;
+pub mod grid;
+#[cfg(any(feature = "quest-07", feature = "quest-16"))]
+mod math;
+mod quests;
+mod variants;
+
+pub use quests::{Answer, Quest, Tag};
+pub use variants::{variants, Variant};
+
+// Quests whose feature is disabled keep their metadata but register a runner which reports them
+// as unavailable.
+macro_rules! quest {
+    ($quest:literal, $feature:literal, $day:ident::$runner:ident, $title:literal, [$($answer:ident),*], [$($tag:ident),*]) => {
+        Quest {
+            year: 2024,
+            quest: $quest,
+            title: $title,
+            answers: &[$(Answer::$answer),*],
+            tags: &[$(Tag::$tag),*],
+            available: cfg!(feature = $feature),
+            runner: {
+                #[cfg(feature = $feature)]
+                const RUNNER: NewRunner =
+                    || Box::new(quests::checked::Checked::new(2024, $quest, $day::$runner::new()));
+                #[cfg(not(feature = $feature))]
+                const RUNNER: NewRunner =
+                    || Box::new(quests::unavailable::Unavailable::new($feature));
+                RUNNER
+            },
+        }
+    };
+}
+
+pub static QUESTS: &[Quest] = &[
+    quest!(
+        1,
+        "quest-01",
+        day_01::Day01,
+        "The Battle for the Farmlands",
+        [Integer, Integer, Integer],
+        [Math]
+    ),
+    quest!(
+        2,
+        "quest-02",
+        day_02::Day02,
+        "The Runes of Power",
+        [Integer, Integer, Integer],
+        [Grid, Strings]
+    ),
+    quest!(
+        3,
+        "quest-03",
+        day_03::Day03,
+        "Mining Maestro",
+        [Integer, Integer, Integer],
+        [Grid, Simulation]
+    ),
+    quest!(
+        4,
+        "quest-04",
+        day_04::Day04,
+        "Royal Smith's Puzzle",
+        [Integer, Integer, Integer],
+        [Math]
+    ),
+    quest!(
+        5,
+        "quest-05",
+        day_05::Day05,
+        "Pseudo-Random Clap Dance",
+        [Integer, Integer, Integer],
+        [Simulation]
+    ),
+    quest!(
+        6,
+        "quest-06",
+        day_06::Day06,
+        "The Tree of Titans",
+        [Text, Text, Text],
+        [Graph]
+    ),
+    quest!(
+        7,
+        "quest-07",
+        day_07::Day07,
+        "Not Fast but Furious",
+        [Text, Text, Integer],
+        [Grid, Simulation]
+    ),
+    quest!(
+        8,
+        "quest-08",
+        day_08::Day08,
+        "A Shrine for Nullpointer",
+        [Integer, Integer, Integer],
+        [Math]
+    ),
+    quest!(
+        9,
+        "quest-09",
+        day_09::Day09,
+        "Sparkling Bugs",
+        [Integer, Integer, Integer],
+        [Math, Search]
+    ),
+    quest!(
+        10,
+        "quest-10",
+        day_10::Day10,
+        "Shrine Needs to Shine",
+        [Text, Integer, Integer],
+        [Grid]
+    ),
+    quest!(
+        11,
+        "quest-11",
+        day_11::Day11,
+        "Biological Warfare",
+        [Integer, Integer, Integer],
+        [Simulation]
+    ),
+    quest!(
+        12,
+        "quest-12",
+        day_12::Day12,
+        "Desert Shower",
+        [Integer, Integer, Integer],
+        [Grid, Math]
+    ),
+    quest!(
+        13,
+        "quest-13",
+        day_13::Day13,
+        "Never Gonna Let You Down",
+        [Integer, Integer, Integer],
+        [Grid, Graph, Search]
+    ),
+    quest!(
+        14,
+        "quest-14",
+        day_14::Day14,
+        "The House of Palms",
+        [Integer, Integer, Integer],
+        [Graph, Search]
+    ),
+    quest!(
+        15,
+        "quest-15",
+        day_15::Day15,
+        "From the Herbalist's Diary",
+        [Integer, Integer, Integer],
+        [Grid, Search]
+    ),
+    quest!(
+        16,
+        "quest-16",
+        day_16::Day16,
+        "Cat's Grand Opening",
+        [Text, Integer, Pair],
+        [Simulation]
+    ),
+    quest!(
+        17,
+        "quest-17",
+        day_17::Day17,
+        "Galactic Geometry",
+        [Integer, Integer, Integer],
+        [Graph]
+    ),
+    quest!(
+        18,
+        "quest-18",
+        day_18::Day18,
+        "The Ring",
+        [Integer, Integer, Integer],
+        [Grid, Search]
+    ),
+    quest!(
+        19,
+        "quest-19",
+        day_19::Day19,
+        "Encrypted Duck",
+        [Text, Text, Text],
+        [Grid, Simulation]
+    ),
+    quest!(
+        20,
+        "quest-20",
+        day_20::Day20,
+        "Gliding Finale",
+        [Integer, Integer, Integer],
+        [Grid, Search]
+    ),
+];
+
+pub fn quest(year: usize, quest: usize) -> Option<&'static Quest> {
+    QUESTS.iter().find(|q| q.year == year && q.quest == quest)
+}
 
 pub fn register(runners: &mut BTreeMap<(usize, usize), (u8, NewRunner)>) {
-    runners.insert((2024, 1), (3, || Box::new(day_01::Day01::new())));
-    runners.insert((2024, 2), (3, || Box::new(day_02::Day02::new())));
-    runners.insert((2024, 3), (3, || Box::new(day_03::Day03::new())));
-    runners.insert((2024, 4), (3, || Box::new(day_04::Day04::new())));
-    runners.insert((2024, 5), (3, || Box::new(day_05::Day05::new())));
-    runners.insert((2024, 6), (3, || Box::new(day_06::Day06::new())));
-    runners.insert((2024, 7), (3, || Box::new(day_07::Day07::new())));
-    runners.insert((2024, 8), (3, || Box::new(day_08::Day08::new())));
-    runners.insert((2024, 9), (3, || Box::new(day_09::Day09::new())));
-    runners.insert((2024, 10), (3, || Box::new(day_10::Day10::new())));
-    runners.insert((2024, 11), (3, || Box::new(day_11::Day11::new())));
-    runners.insert((2024, 12), (3, || Box::new(day_12::Day12::new())));
-    runners.insert((2024, 13), (3, || Box::new(day_13::Day13::new())));
-    runners.insert((2024, 14), (3, || Box::new(day_14::Day14::new())));
-    runners.insert((2024, 15), (3, || Box::new(day_15::Day15::new())));
-    runners.insert((2024, 16), (3, || Box::new(day_16::Day16::new())));
-    runners.insert((2024, 17), (3, || Box::new(day_17::Day17::new())));
-    runners.insert((2024, 18), (3, || Box::new(day_18::Day18::new())));
-    runners.insert((2024, 19), (3, || Box::new(day_19::Day19::new())));
-    runners.insert((2024, 20), (3, || Box::new(day_20::Day20::new())));
+    for quest in QUESTS.iter() {
+        runners.insert((quest.year, quest.quest), (quest.parts(), quest.runner));
+    }
 }
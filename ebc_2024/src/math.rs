@@ -0,0 +1,8 @@
+/// Greatest common divisor, `a` itself when `b` is 0.
+pub(crate) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,227 @@
+#[allow(unused_imports)]
+use helper::{print, println, Error, NewRunner};
+use std::str::FromStr;
+
+/// Shape of the answer a quest part is expected to produce.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Integer,
+    Text,
+    /// Two integers separated by a single space, e.g. `"627 57"`.
+    Pair,
+}
+
+impl Answer {
+    pub fn matches(&self, answer: &str) -> bool {
+        fn is_integer(s: &str) -> bool {
+            let s = s.strip_prefix('-').unwrap_or(s);
+            !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+        }
+        match self {
+            Self::Integer => is_integer(answer),
+            Self::Text => !answer.is_empty(),
+            Self::Pair => answer
+                .split_once(' ')
+                .is_some_and(|(a, b)| is_integer(a) && is_integer(b)),
+        }
+    }
+}
+
+impl std::fmt::Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer => write!(f, "integer"),
+            Self::Text => write!(f, "text"),
+            Self::Pair => write!(f, "pair"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Grid,
+    Graph,
+    Math,
+    Search,
+    Simulation,
+    Strings,
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Grid => write!(f, "grid"),
+            Self::Graph => write!(f, "graph"),
+            Self::Math => write!(f, "math"),
+            Self::Search => write!(f, "search"),
+            Self::Simulation => write!(f, "simulation"),
+            Self::Strings => write!(f, "strings"),
+        }
+    }
+}
+
+impl FromStr for Tag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "grid" => Ok(Self::Grid),
+            "graph" => Ok(Self::Graph),
+            "math" => Ok(Self::Math),
+            "search" => Ok(Self::Search),
+            "simulation" => Ok(Self::Simulation),
+            "strings" => Ok(Self::Strings),
+            _ => Err(Error::InvalidInput(s.into())),
+        }
+    }
+}
+
+pub struct Quest {
+    pub year: usize,
+    pub quest: usize,
+    pub title: &'static str,
+    pub answers: &'static [Answer],
+    pub tags: &'static [Tag],
+    /// False when the quest's cargo feature is disabled.
+    pub available: bool,
+    pub runner: NewRunner,
+}
+
+impl Quest {
+    pub fn parts(&self) -> u8 {
+        self.answers.len() as u8
+    }
+
+    pub fn has_tag(&self, tag: Tag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+impl std::fmt::Display for Quest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} Quest {:02}: {}", self.year, self.quest, self.title)?;
+        for (idx, tag) in self.tags.iter().enumerate() {
+            write!(f, "{}{tag}", if idx == 0 { " [" } else { ", " })?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+// Only used by quests whose feature is enabled
+#[cfg(any(
+    feature = "quest-01",
+    feature = "quest-02",
+    feature = "quest-03",
+    feature = "quest-04",
+    feature = "quest-05",
+    feature = "quest-06",
+    feature = "quest-07",
+    feature = "quest-08",
+    feature = "quest-09",
+    feature = "quest-10",
+    feature = "quest-11",
+    feature = "quest-12",
+    feature = "quest-13",
+    feature = "quest-14",
+    feature = "quest-15",
+    feature = "quest-16",
+    feature = "quest-17",
+    feature = "quest-18",
+    feature = "quest-19",
+    feature = "quest-20"
+))]
+pub(crate) mod checked {
+    use helper::Error;
+
+    /// Wraps a quest's runner and fails any answer that doesn't have the shape
+    /// declared in the quest's metadata.
+    pub(crate) struct Checked<R> {
+        year: usize,
+        quest: usize,
+        inner: R,
+    }
+
+    impl<R> Checked<R> {
+        pub(crate) fn new(year: usize, quest: usize, inner: R) -> Self {
+            Self { year, quest, inner }
+        }
+    }
+
+    impl<R: helper::Runner> helper::Runner for Checked<R> {
+        fn parse(&mut self, file: &[u8], part: u8) -> Result<(), Error> {
+            self.inner.parse(file, part)
+        }
+
+        fn run_part(&mut self, part: u8) -> Result<helper::RunOutput, Error> {
+            let output = self.inner.run_part(part)?;
+            if let Some(quest) = crate::quest(self.year, self.quest) {
+                let answer = (part as usize)
+                    .checked_sub(1)
+                    .and_then(|idx| quest.answers.get(idx));
+                if let Some(answer) = answer {
+                    let got = output.to_string();
+                    if !answer.matches(&got) {
+                        return Err(Error::InvalidInput(format!(
+                            "{} Quest {:02} part {part}: expected {answer} answer, got {got:?}",
+                            self.year, self.quest,
+                        )));
+                    }
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+// Only used by quests whose feature is disabled
+#[cfg(not(all(
+    feature = "quest-01",
+    feature = "quest-02",
+    feature = "quest-03",
+    feature = "quest-04",
+    feature = "quest-05",
+    feature = "quest-06",
+    feature = "quest-07",
+    feature = "quest-08",
+    feature = "quest-09",
+    feature = "quest-10",
+    feature = "quest-11",
+    feature = "quest-12",
+    feature = "quest-13",
+    feature = "quest-14",
+    feature = "quest-15",
+    feature = "quest-16",
+    feature = "quest-17",
+    feature = "quest-18",
+    feature = "quest-19",
+    feature = "quest-20"
+)))]
+pub(crate) mod unavailable {
+    #[allow(unused_imports)]
+    use helper::{print, println, Error};
+
+    /// Stands in for a quest whose cargo feature is disabled.  Every part answers with a note
+    /// saying which feature to build with, rather than failing.
+    pub(crate) struct Unavailable {
+        feature: &'static str,
+    }
+
+    impl Unavailable {
+        pub(crate) fn new(feature: &'static str) -> Self {
+            Self { feature }
+        }
+    }
+
+    impl helper::Runner for Unavailable {
+        fn parse(&mut self, _file: &[u8], _part: u8) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn run_part(&mut self, _part: u8) -> Result<helper::RunOutput, Error> {
+            Ok(format!("unavailable, build with feature `{}`", self.feature).into())
+        }
+    }
+}
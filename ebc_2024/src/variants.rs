@@ -0,0 +1,147 @@
+#[allow(unused_imports)]
+use helper::{print, println, Error, NewRunner};
+
+#[derive(Copy, Clone)]
+enum Solver {
+    Runner(NewRunner),
+    // Only built when some quest with an alternate is enabled
+    #[cfg(any(
+        feature = "quest-02",
+        feature = "quest-03",
+        feature = "quest-04",
+        feature = "quest-05",
+        feature = "quest-06",
+        feature = "quest-07",
+        feature = "quest-12",
+        feature = "quest-14",
+        feature = "quest-15",
+        feature = "quest-16"
+    ))]
+    Alternate(fn(&[u8], u8) -> Result<helper::RunOutput, Error>),
+}
+
+/// A named implementation of a single quest part.  The quest's registered
+/// runner is always available as the `default` variant.
+#[derive(Copy, Clone)]
+pub struct Variant {
+    pub year: usize,
+    pub quest: usize,
+    pub part: u8,
+    pub name: &'static str,
+    solver: Solver,
+}
+
+impl Variant {
+    pub fn run(&self, file: &[u8]) -> Result<helper::RunOutput, Error> {
+        match self.solver {
+            Solver::Runner(new) => {
+                let mut runner = new();
+                runner.parse(file, self.part)?;
+                runner.run_part(self.part)
+            }
+            #[cfg(any(
+                feature = "quest-02",
+                feature = "quest-03",
+                feature = "quest-04",
+                feature = "quest-05",
+                feature = "quest-06",
+                feature = "quest-07",
+                feature = "quest-12",
+                feature = "quest-14",
+                feature = "quest-15",
+                feature = "quest-16"
+            ))]
+            Solver::Alternate(run) => run(file, self.part),
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "quest-02",
+    feature = "quest-03",
+    feature = "quest-04",
+    feature = "quest-05",
+    feature = "quest-06",
+    feature = "quest-07",
+    feature = "quest-12",
+    feature = "quest-14",
+    feature = "quest-15",
+    feature = "quest-16"
+))]
+macro_rules! variant {
+    ($quest:literal, $part:literal, $name:literal, $day:ident::$runner:ident::$func:ident) => {
+        Variant {
+            year: 2024,
+            quest: $quest,
+            part: $part,
+            name: $name,
+            solver: Solver::Alternate(|file, part| {
+                let mut day = crate::$day::$runner::new();
+                helper::Runner::parse(&mut day, file, part)?;
+                Ok(day.$func()?.into())
+            }),
+        }
+    };
+}
+
+static ALTERNATES: &[Variant] = &[
+    #[cfg(feature = "quest-02")]
+    variant!(2, 2, "brute", day_02::Day02::part2_brute),
+    #[cfg(feature = "quest-02")]
+    variant!(2, 3, "brute", day_02::Day02::part3_brute),
+    #[cfg(feature = "quest-03")]
+    variant!(3, 2, "edge", day_03::Day03::part2_edge),
+    #[cfg(feature = "quest-03")]
+    variant!(3, 3, "edge", day_03::Day03::part3_edge),
+    #[cfg(feature = "quest-04")]
+    variant!(4, 3, "scan", day_04::Day04::part3_scan),
+    #[cfg(feature = "quest-05")]
+    variant!(5, 2, "dance", day_05::Day05::part2_dance),
+    #[cfg(feature = "quest-06")]
+    variant!(6, 1, "bfs", day_06::Day06::part1_bfs),
+    #[cfg(feature = "quest-06")]
+    variant!(6, 2, "bfs", day_06::Day06::part2_bfs),
+    #[cfg(feature = "quest-06")]
+    variant!(6, 3, "bfs", day_06::Day06::part3_bfs),
+    #[cfg(feature = "quest-07")]
+    variant!(7, 2, "laps", day_07::Day07::part2_laps),
+    #[cfg(feature = "quest-07")]
+    variant!(7, 3, "permutations", day_07::Day07::part3_permutations),
+    #[cfg(feature = "quest-12")]
+    variant!(12, 3, "simulate", day_12::Day12::part3_simulate),
+    #[cfg(feature = "quest-14")]
+    variant!(14, 3, "leaves", day_14::Day14::part3_leaves),
+    #[cfg(feature = "quest-15")]
+    variant!(15, 2, "states", day_15::Day15::part2_states),
+    #[cfg(feature = "quest-15")]
+    variant!(15, 3, "states", day_15::Day15::part3_states),
+    #[cfg(feature = "quest-16")]
+    variant!(16, 2, "period", day_16::Day16::part2_period),
+    #[cfg(feature = "quest-16")]
+    variant!(16, 3, "states", day_16::Day16::part3_states),
+];
+
+/// All implementations of a quest part, starting with the `default` variant.
+pub fn variants(year: usize, quest: usize, part: u8) -> Vec<Variant> {
+    let Some(quest) = crate::quest(year, quest) else {
+        return Vec::new();
+    };
+    if !quest.available || part == 0 || part > quest.parts() {
+        return Vec::new();
+    }
+
+    let mut variants = vec![Variant {
+        year,
+        quest: quest.quest,
+        part,
+        name: "default",
+        solver: Solver::Runner(quest.runner),
+    }];
+    variants.extend(
+        ALTERNATES
+            .iter()
+            .filter(|v| v.year == year && v.quest == quest.quest && v.part == part)
+            .copied(),
+    );
+    variants
+}
@@ -0,0 +1,304 @@
+use helper::Error;
+
+// Debugging views of a quest beyond its answers.
+//
+// Usage: inspect <quest> <input file> <mode> [args...]
+//   5 <input> after <rounds>   lineup and shout after any number of rounds
+//   6 <input> check            cycles, orphans and repeated parents, then the best path or why
+//                              there isn't one
+//   6 <input> dot <path>       the tree as Graphviz with the best path in red
+//   7 <input> plans [laps] [actions]
+//                              rank every ordering of the actions (default `+5,-3,=3`) against
+//                              the devices
+//   7 <input> tracks           segments of every track in racing order
+//   7 <input> race [laps] [plan|-] [svg file]
+//                              totals and ranking per lap on every track, optionally racing an
+//                              extra plan as `P`, with the chart of track N after the first in
+//                              `<svg stem>-N.svg`
+//  12 <input> plan [shots]    targets left and destroyed after some (or all) shots of the plan
+//  13 <input> starts [levels] [step] [teleport]
+//                              quickest time from every start, optionally with other maze rules
+//  14 <input> export <path>  grown tree as <path>.obj and <path>.ply, murkiness as <path>.csv
+//  14 <input> check [strict]  every zero step, dip below ground, crossing and loose piece,
+//                              failing like strict parsing would when asked
+//  15 <input> route           shortest herb tour drawn over the map
+//  16 <input> levers [pulls]  best and worst scores with the levers pulled to get them
+type Inspector = fn(&[u8], &str, &[String]) -> Result<(), Error>;
+
+static INSPECTORS: &[(usize, Inspector)] = &[
+    #[cfg(feature = "quest-05")]
+    (5, quest_05),
+    #[cfg(feature = "quest-06")]
+    (6, quest_06),
+    #[cfg(feature = "quest-07")]
+    (7, quest_07),
+    #[cfg(feature = "quest-12")]
+    (12, quest_12),
+    #[cfg(feature = "quest-13")]
+    (13, quest_13),
+    #[cfg(feature = "quest-14")]
+    (14, quest_14),
+    #[cfg(feature = "quest-15")]
+    (15, quest_15),
+    #[cfg(feature = "quest-16")]
+    (16, quest_16),
+];
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        eprintln!("Usage: inspect <quest> <input file> <mode> [args...]");
+        return Err(Error::InvalidInput(args.join(" ")));
+    }
+    let quest: usize = args[0].parse()?;
+    let notes =
+        std::fs::read(&args[1]).map_err(|e| Error::InvalidInput(format!("{}: {e}", args[1])))?;
+
+    let (_, inspector) = INSPECTORS
+        .iter()
+        .find(|(q, _)| *q == quest)
+        .ok_or(Error::Skipped)?;
+    inspector(&notes, &args[2], &args[3..])
+}
+
+#[cfg(feature = "quest-05")]
+fn quest_05(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_05::Day05;
+
+    let day = Day05::from_notes(notes)?;
+    match mode {
+        "after" => {
+            let rounds = args.first().map(|r| r.parse()).transpose()?.unwrap_or(10);
+            let state = day.state_after(rounds)?;
+            println!("After round {}: shout {}", state.round, state.shout);
+            for (idx, column) in state.columns.iter().enumerate() {
+                let column: Vec<String> = column.iter().map(|n| n.to_string()).collect();
+                println!("  column {}: {}", idx + 1, column.join(" "));
+            }
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-06")]
+fn quest_06(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_06::Day06;
+
+    let day = Day06::from_notes(notes)?;
+    match mode {
+        "check" => {
+            for diagnostic in day.diagnostics() {
+                println!("{diagnostic}");
+            }
+            match day.best_fruit() {
+                Ok(fruit) => println!("Best path: {}", day.path_to(fruit).join("")),
+                Err(Error::InvalidInput(reason)) => println!("{reason}"),
+                Err(e) => return Err(e),
+            }
+        }
+        "dot" => {
+            let path = args
+                .first()
+                .ok_or_else(|| Error::InvalidInput("dot needs a path".into()))?;
+            day.write_dot(path)?;
+            println!("{} nodes written to {path}", day.nodes().len());
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-07")]
+fn quest_07(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_07::{Command, Day07};
+
+    let mut day = Day07::from_notes(notes)?;
+    match mode {
+        "plans" => {
+            let laps = args.first().map(|l| l.parse()).transpose()?.unwrap_or(2024);
+            let mut actions = [(Command::Inc, 5), (Command::Dec, 3), (Command::Stay, 3)];
+            if let Some(counts) = args.get(1) {
+                actions.iter_mut().for_each(|(_, count)| *count = 0);
+                for action in counts.split(',') {
+                    let (command, count) = action
+                        .split_at_checked(1)
+                        .ok_or_else(|| Error::InvalidInput(format!("Bad action {action:?}")))?;
+                    let command: Command = command.parse()?;
+                    let slot = actions
+                        .iter_mut()
+                        .find(|(c, _)| *c == command)
+                        .ok_or_else(|| Error::InvalidInput(format!("Bad action {action:?}")))?;
+                    slot.1 = count.parse()?;
+                }
+            }
+            let report = day.optimize(actions, laps);
+            print!("{report}");
+        }
+        "race" => {
+            let laps = args.first().map(|l| l.parse()).transpose()?.unwrap_or(10);
+            if let Some(plan) = args.get(1).filter(|p| *p != "-") {
+                day.add_device(format!("P:{plan}").parse()?);
+            }
+            print!("{}", day.render_track());
+            for (idx, track) in day.tracks().iter().enumerate() {
+                let progress = day.race_progress(idx, laps)?;
+                println!("Track from S at ({}, {})", track.start.0, track.start.1);
+                print!("{progress}");
+                if let Some(svg) = args.get(2) {
+                    let svg = match (idx, svg.strip_suffix(".svg")) {
+                        (0, _) => svg.clone(),
+                        (_, Some(stem)) => format!("{stem}-{}.svg", idx + 1),
+                        (_, None) => format!("{svg}-{}", idx + 1),
+                    };
+                    progress
+                        .write_svg(&svg)
+                        .map_err(|e| Error::InvalidInput(format!("{svg}: {e}")))?;
+                }
+            }
+        }
+        "tracks" => {
+            for track in day.tracks() {
+                let segments: String = track.segments.iter().map(|c| format!("{c:?}")).collect();
+                println!(
+                    "S at ({}, {}), {} segments: {segments}",
+                    track.start.0,
+                    track.start.1,
+                    track.segments.len()
+                );
+            }
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-12")]
+fn quest_12(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_12::Day12;
+
+    let day = Day12::from_notes(notes)?;
+    match mode {
+        "plan" => {
+            let plan = day.shot_plan()?;
+            let shots = args.first().map(|s| s.parse()).transpose()?;
+            print!(
+                "{}",
+                day.render_plan(&plan, shots.unwrap_or(plan.shots.len()))
+            );
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-13")]
+fn quest_13(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_13::{Day13, Rules};
+
+    let day = Day13::from_notes(notes)?;
+    match mode {
+        "starts" => {
+            let arg = |idx: usize, default: u32| -> Result<u32, Error> {
+                Ok(args
+                    .get(idx)
+                    .map(|a| a.parse())
+                    .transpose()?
+                    .unwrap_or(default))
+            };
+            let day = day.with_rules(Rules {
+                levels: arg(0, Rules::QUEST.levels)?,
+                step: arg(1, Rules::QUEST.step)?,
+                teleport: arg(2, Rules::QUEST.teleport)?,
+            })?;
+            for ((x, y), time) in day.start_times() {
+                match time {
+                    Some(time) => println!("S at ({x}, {y}): {time}"),
+                    None => println!("S at ({x}, {y}): can't reach an E"),
+                }
+            }
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-14")]
+fn quest_14(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_14::Day14;
+
+    let day = Day14::from_notes(notes)?;
+    match mode {
+        "export" => {
+            let path = args
+                .first()
+                .ok_or_else(|| Error::InvalidInput("export needs a path".into()))?;
+            let tree = day.tree();
+            let mesh = tree.mesh();
+            mesh.write_obj(format!("{path}.obj"))?;
+            mesh.write_ply(format!("{path}.ply"))?;
+            std::fs::write(format!("{path}.csv"), day.murkiness_csv())?;
+            println!(
+                "{} cells, {} leaves: {} faces written to {path}.obj and {path}.ply, murkiness to {path}.csv",
+                tree.cells.len(),
+                tree.leaves.len(),
+                mesh.faces.len()
+            );
+        }
+        "check" => {
+            let problems = day.growth_problems();
+            for problem in problems.iter() {
+                println!("{problem}");
+            }
+            println!(
+                "{} problems in {} branches",
+                problems.len(),
+                day.branches().len()
+            );
+            if args.first().is_some_and(|arg| arg == "strict") {
+                day.strict()?;
+            }
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-15")]
+fn quest_15(notes: &[u8], mode: &str, _args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_15::Day15;
+
+    let day = Day15::from_notes(notes)?;
+    match mode {
+        "route" => print!("{}", day.render_route(&day.shortest_route()?)),
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "quest-16")]
+fn quest_16(notes: &[u8], mode: &str, args: &[String]) -> Result<(), Error> {
+    use ebc_2024::day_16::Day16;
+
+    let day = Day16::from_notes(notes)?;
+    match mode {
+        "levers" => {
+            let pulls = args.first().map(|p| p.parse()).transpose()?.unwrap_or(256);
+            let report = day.pull_levers(pulls, true);
+            let levers = |levers: &[i8]| -> String {
+                levers
+                    .iter()
+                    .map(|lever| match lever {
+                        -1 => '<',
+                        1 => '>',
+                        _ => '.',
+                    })
+                    .collect()
+            };
+            println!("Max {}: {}", report.max, levers(&report.max_levers));
+            println!("Min {}: {}", report.min, levers(&report.min_levers));
+        }
+        _ => return Err(Error::Skipped),
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use ebc_2024::generators::{self, Rng};
+use helper::Error;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// Generates random notes for every quest with a generator (or just the given one) and checks that
+// all variants of each part agree on them, and that none of them panics.  Without an iteration
+// count each part is checked on as many notes as its generator suggests, fewer for parts with
+// slow reference implementations.
+//
+// Usage: stress [quest[:part]] [iterations] [size] [seed]
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (quest, part): (Option<usize>, Option<u8>) = match args.first() {
+        Some(arg) => match arg.split_once(':') {
+            Some((quest, part)) => (Some(quest.parse()?), Some(part.parse()?)),
+            None => (Some(arg.parse()?), None),
+        },
+        None => (None, None),
+    };
+    let iterations: Option<usize> = args.get(1).map(|i| i.parse()).transpose()?;
+    let size: usize = args.get(2).map(|s| s.parse()).transpose()?.unwrap_or(6);
+    let seed: u64 = args.get(3).map(|s| s.parse()).transpose()?.unwrap_or(2024);
+
+    // Keep panics from variants quiet, they are reported along with the notes that caused them
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut failures = 0;
+    for (year, q) in generators::generated_quests() {
+        if quest.is_some_and(|quest| quest != q) {
+            continue;
+        }
+        let generator = generators::generator(year, q).unwrap();
+        let Some(info) = ebc_2024::quest(year, q) else {
+            continue;
+        };
+        let parts: Vec<u8> = (1..=info.parts())
+            .filter(|p| part.is_none_or(|part| part == *p))
+            .filter(|part| !ebc_2024::variants(year, q, *part).is_empty())
+            .collect();
+        let counts: Vec<usize> = parts
+            .iter()
+            .map(|part| iterations.unwrap_or_else(|| generators::iterations(year, q, *part)))
+            .collect();
+
+        let mut rng = Rng::new(seed);
+        let mut quest_failures = 0;
+        for iteration in 0..counts.iter().copied().max().unwrap_or(0) {
+            let notes = generator(&mut rng, size);
+            for (part, _) in parts
+                .iter()
+                .copied()
+                .zip(counts.iter())
+                .filter(|(_, count)| iteration < **count)
+            {
+                let answers: Vec<(&str, String)> = ebc_2024::variants(year, q, part)
+                    .iter()
+                    .map(|variant| {
+                        let answer = match catch_unwind(AssertUnwindSafe(|| {
+                            variant.run(notes.as_bytes())
+                        })) {
+                            Ok(Ok(output)) => output.to_string(),
+                            Ok(Err(e)) => format!("{e:?}"),
+                            Err(_) => "panicked".to_string(),
+                        };
+                        (variant.name, answer)
+                    })
+                    .collect();
+
+                let panicked = answers.iter().any(|(_, a)| a == "panicked");
+                if panicked || answers.iter().any(|(_, a)| *a != answers[0].1) {
+                    quest_failures += 1;
+                    if quest_failures <= 3 {
+                        let problem = if panicked { "panicked" } else { "disagree" };
+                        println!("{info} part {part} variants {problem}:");
+                        for (name, answer) in answers.iter() {
+                            println!("  {name:>16}: {answer}");
+                        }
+                        println!("{notes}");
+                    }
+                }
+            }
+        }
+        println!("{info}");
+        println!("  parts {parts:?}: {counts:?} inputs, {quest_failures} failures");
+        failures += quest_failures;
+    }
+
+    if failures != 0 {
+        Err(Error::Unsolved)
+    } else {
+        Ok(())
+    }
+}
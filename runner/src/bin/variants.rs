@@ -0,0 +1,48 @@
+use helper::Error;
+use std::time::Instant;
+
+// Runs every implementation of a quest part against the same input, or only the named one, and
+// checks that their answers agree.
+//
+// Usage: variants <year> <quest> <part> <input file> [variant]
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 4 || args.len() > 5 {
+        eprintln!("Usage: variants <year> <quest> <part> <input file> [variant]");
+        return Err(Error::InvalidInput(args.join(" ")));
+    }
+    let year: usize = args[0].parse()?;
+    let quest: usize = args[1].parse()?;
+    let part: u8 = args[2].parse()?;
+    let file =
+        std::fs::read(&args[3]).map_err(|e| Error::InvalidInput(format!("{}: {e}", args[3])))?;
+
+    let mut variants = ebc_2024::variants(year, quest, part);
+    if let Some(name) = args.get(4) {
+        variants.retain(|v| v.name == name);
+    }
+    if variants.is_empty() {
+        return Err(Error::Skipped);
+    }
+    if let Some(quest) = ebc_2024::quest(year, quest) {
+        println!("{quest} part {part}");
+    }
+
+    let mut answers: Vec<String> = Vec::new();
+    for variant in variants.iter() {
+        let start = Instant::now();
+        let answer = match variant.run(&file) {
+            Ok(output) => output.to_string(),
+            Err(e) => format!("{e:?}"),
+        };
+        let elapsed = start.elapsed();
+        println!("{:>16}: {answer:<24} {elapsed:?}", variant.name);
+        answers.push(answer);
+    }
+
+    if answers.iter().any(|a| *a != answers[0]) {
+        println!("Variants disagree");
+        return Err(Error::Unsolved);
+    }
+    Ok(())
+}
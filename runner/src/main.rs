@@ -1,5 +1,6 @@
 use helper::{Error, NewRunner};
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
 const README_HEADER: &str = "[Everybody Codes](https://everybody.codes/)
 Michael Conrad
@@ -7,8 +8,35 @@ Michael Conrad
 [Helper library](https://github.com/mmitton/helper) which holds generic algorithms and runner
 infrastructure shared between AOC and Everybody Codes";
 
+// Comma separated list of tags (see `ebc_2024::Tag`) used to restrict which quests are registered
+const TAGS_VAR: &str = "EBC_TAGS";
+
+// The tags in `EBC_TAGS`, if it is set and they all parse.
+fn tags() -> Option<&'static [ebc_2024::Tag]> {
+    static TAGS: OnceLock<Option<Vec<ebc_2024::Tag>>> = OnceLock::new();
+    TAGS.get_or_init(|| {
+        let tags = std::env::var(TAGS_VAR).ok()?;
+        match tags.split(',').map(|tag| tag.parse()).collect() {
+            Ok(tags) => Some(tags),
+            Err(e) => {
+                eprintln!("Ignoring {TAGS_VAR}: {e:?}");
+                None
+            }
+        }
+    })
+    .as_deref()
+}
+
 fn register(runners: &mut BTreeMap<(usize, usize), (u8, NewRunner)>) {
     ebc_2024::register(runners);
+
+    let Some(tags) = tags() else {
+        return;
+    };
+    runners.retain(|(year, quest), _| {
+        ebc_2024::quest(*year, *quest)
+            .is_some_and(|quest| tags.iter().any(|tag| quest.has_tag(*tag)))
+    });
 }
 
 fn today(year: usize, month: usize, day: usize) -> (usize, usize) {
@@ -39,7 +67,7 @@ fn today(year: usize, month: usize, day: usize) -> (usize, usize) {
     match month.cmp(&11) {
         Ordering::Less => (year - 1, 20),
         Ordering::Equal => {
-            if let Some((_, day)) = days.iter().filter(|(d, _)| *d <= day).last() {
+            if let Some((_, day)) = days.iter().rfind(|(d, _)| *d <= day) {
                 (year, *day)
             } else {
                 (year - 1, 20)
@@ -54,5 +82,9 @@ fn main() -> Result<(), Error> {
     config.download_input(false);
     config.allow_copy(false);
     config.readme_header(README_HEADER);
-    helper::runner::main::<_, _, 3>(config)
+    match helper::runner::main::<_, _, 3>(config) {
+        // Quests left out by the tags aren't registered, which the helper reports as skipped
+        Err(Error::Skipped) if tags().is_some() => Ok(()),
+        result => result,
+    }
 }